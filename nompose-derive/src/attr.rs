@@ -0,0 +1,179 @@
+//! The `#[termpose(...)]` attributes
+
+use syn::{Attribute, Data, DeriveInput, ExprPath, Ident, LitStr, Result};
+
+/// How to spell field and variant names which aren't renamed
+#[derive(Clone, Copy)]
+pub enum Case {
+    /// As written in Rust
+    Verbatim,
+    Lower,
+    Snake,
+    Kebab,
+}
+
+impl Case {
+    /// Spell an identifier in this case
+    pub fn apply(self, ident: &Ident) -> String {
+        let name = ident.to_string();
+        let name = name.trim_start_matches("r#");
+        match self {
+            Case::Verbatim => name.into(),
+            Case::Lower => name.to_lowercase(),
+            Case::Snake => words(name).join("_"),
+            Case::Kebab => words(name).join("-"),
+        }
+    }
+}
+
+/// The lowercase words of a `snake_case` or `CamelCase` name
+fn words(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut word = String::new();
+        for c in part.chars() {
+            if c.is_uppercase() && !word.is_empty() {
+                words.push(word);
+                word = String::new();
+            }
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    words
+}
+
+/// Attributes on a struct or enum
+pub struct Container {
+    pub rename: Option<String>,
+    pub rename_all: Case,
+}
+
+impl Container {
+    pub fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut container = Self {
+            rename: None,
+            rename_all: Case::Verbatim,
+        };
+
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("termpose"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Data::Enum(_) = input.data {
+                        return Err(meta.error(
+                            "enums are written as their variants, so have no name to rename",
+                        ));
+                    }
+                    container.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    let case = meta.value()?.parse::<LitStr>()?;
+                    container.rename_all = match case.value().as_str() {
+                        "lowercase" => Case::Lower,
+                        "snake_case" => Case::Snake,
+                        "kebab-case" => Case::Kebab,
+                        _ => {
+                            return Err(syn::Error::new(
+                                case.span(),
+                                "expected \"lowercase\", \"snake_case\" or \"kebab-case\"",
+                            ))
+                        }
+                    };
+                } else {
+                    return Err(meta.error("unknown termpose attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(container)
+    }
+}
+
+/// What to use for a field whose key is missing
+pub enum Default {
+    /// Whatever the field's type does about it
+    None,
+
+    /// `Default::default()`
+    Trait,
+
+    /// The result of calling a function
+    Path(ExprPath),
+}
+
+/// Attributes on a field
+pub struct Field {
+    pub rename: Option<String>,
+    pub default: Default,
+    pub flatten: bool,
+    pub positional: bool,
+}
+
+impl Field {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut field = Self {
+            rename: None,
+            default: Default::None,
+            flatten: false,
+            positional: false,
+        };
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("termpose")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    field.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    field.default = if meta.input.peek(syn::Token![=]) {
+                        Default::Path(meta.value()?.parse::<LitStr>()?.parse()?)
+                    } else {
+                        Default::Trait
+                    };
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                } else if meta.path.is_ident("positional") {
+                    field.positional = true;
+                } else {
+                    return Err(meta.error("unknown termpose attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if field.flatten && (field.positional || field.rename.is_some()) {
+            let attr = attrs.iter().find(|attr| attr.path().is_ident("termpose"));
+            return Err(syn::Error::new_spanned(
+                attr,
+                "flattened fields have no key or position of their own",
+            ));
+        }
+
+        Ok(field)
+    }
+}
+
+/// Attributes on an enum variant
+pub struct Variant {
+    pub rename: Option<String>,
+}
+
+impl Variant {
+    pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut variant = Self { rename: None };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("termpose")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    variant.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown termpose attribute"))
+                }
+            })?;
+        }
+
+        Ok(variant)
+    }
+}
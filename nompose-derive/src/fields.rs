@@ -0,0 +1,79 @@
+//! The fields of structs and enum variants
+
+use attr::{self, Case};
+use proc_macro2::{Ident, Span};
+use syn::{Fields, Member, Result, Type};
+
+/// How a struct or variant holds its fields
+pub enum Shape {
+    Unit,
+
+    /// A single unnamed field, which stands in for the whole
+    Newtype,
+
+    /// Fields read from keys, positions, or flattened structs
+    Fields,
+}
+
+pub struct Field {
+    pub member: Member,
+
+    /// What the field's value is bound to while decoding or encoding
+    pub binding: Ident,
+    pub ty: Type,
+
+    /// The key it's read from, or for positional fields, its name in errors
+    pub key: String,
+    pub attrs: attr::Field,
+}
+
+pub fn shape(fields: &Fields) -> Result<Shape> {
+    Ok(match fields {
+        Fields::Unit => Shape::Unit,
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let field = &unnamed.unnamed[0];
+            let attrs = attr::Field::parse(&field.attrs)?;
+            let default = !matches!(attrs.default, attr::Default::None);
+            if attrs.flatten || attrs.positional || default || attrs.rename.is_some() {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "a single unnamed field stands in for the whole, so takes no attributes",
+                ));
+            }
+            Shape::Newtype
+        }
+        _ => Shape::Fields,
+    })
+}
+
+/// Read the fields of a struct or variant, spelling keys in `case`
+pub fn parse(fields: &Fields, case: Case) -> Result<Vec<Field>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let mut attrs = attr::Field::parse(&field.attrs)?;
+            let (member, name) = if let Some(ident) = &field.ident {
+                (Member::Named(ident.clone()), case.apply(ident))
+            } else {
+                attrs.positional = true;
+                (Member::Unnamed(i.into()), i.to_string())
+            };
+
+            Ok(Field {
+                member,
+                binding: Ident::new(&format!("field{i}"), Span::call_site()),
+                ty: field.ty.clone(),
+                key: attrs.rename.clone().unwrap_or(name),
+                attrs,
+            })
+        })
+        .collect()
+}
+
+/// Fields in the order they're decoded and encoded: positional fields first
+pub fn ordered(fields: &[Field]) -> impl Iterator<Item = &Field> {
+    let (positional, rest): (Vec<&Field>, Vec<&Field>) =
+        fields.iter().partition(|field| field.attrs.positional);
+    positional.into_iter().chain(rest)
+}
@@ -0,0 +1,207 @@
+//! `#[derive(FromTermpose)]`
+
+use attr::{self, Container};
+use fields::{self, Field, Shape};
+use proc_macro2::TokenStream;
+use syn::{Data, DataEnum, DeriveInput, Fields, Result};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::parse(input)?;
+    let body = match &input.data {
+        Data::Struct(data) => structure(&data.fields, &container)?,
+        Data::Enum(data) => enumeration(data, &container)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "unions can't derive FromTermpose",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let generics = ::bounded(&input.generics, &quote!(::nompose::FromTermpose));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::nompose::FromTermpose for #name #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+fn structure(data: &Fields, container: &Container) -> Result<TokenStream> {
+    if let Shape::Newtype = fields::shape(data)? {
+        return Ok(quote! {
+            fn from_tail(
+                tail: &[::nompose::Node],
+                line: usize,
+            ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+                ::nompose::FromTermpose::from_tail(tail, line).map(Self)
+            }
+
+            fn from_item(
+                node: &::nompose::Node,
+            ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+                ::nompose::FromTermpose::from_item(node).map(Self)
+            }
+
+            fn from_missing(
+                key: &str,
+                line: usize,
+            ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+                ::nompose::FromTermpose::from_missing(key, line).map(Self)
+            }
+        });
+    }
+
+    let fields = fields::parse(data, container.rename_all)?;
+    let reads = reads(&fields);
+    let construct = construct(&quote!(Self), data, &fields);
+    Ok(quote! {
+        fn from_tail(
+            tail: &[::nompose::Node],
+            line: usize,
+        ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+            let entries = &mut ::nompose::convert::Entries::new(tail, line);
+            let value = <Self as ::nompose::FromTermpose>::from_entries(entries)?;
+            entries.finish()?;
+            ::std::result::Result::Ok(value)
+        }
+
+        fn from_item(
+            node: &::nompose::Node,
+        ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+            <Self as ::nompose::FromTermpose>::from_tail(&node.children, node.line)
+        }
+
+        fn from_entries(
+            entries: &mut ::nompose::convert::Entries,
+        ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+            #(#reads)*
+            ::std::result::Result::Ok(#construct)
+        }
+    })
+}
+
+fn enumeration(data: &DataEnum, container: &Container) -> Result<TokenStream> {
+    let mut arms = vec![];
+    for variant in &data.variants {
+        let attrs = attr::Variant::parse(&variant.attrs)?;
+        let key = attrs
+            .rename
+            .unwrap_or_else(|| container.rename_all.apply(&variant.ident));
+        let ident = &variant.ident;
+
+        let body = match fields::shape(&variant.fields)? {
+            Shape::Unit => quote! {
+                ::nompose::convert::Entries::new(&node.children, node.line).finish()?;
+                ::std::result::Result::Ok(Self::#ident)
+            },
+            Shape::Newtype => quote! {
+                ::nompose::FromTermpose::from_tail(&node.children, node.line).map(Self::#ident)
+            },
+            Shape::Fields => {
+                let fields = fields::parse(&variant.fields, container.rename_all)?;
+                let reads = reads(&fields);
+                let construct = construct(&quote!(Self::#ident), &variant.fields, &fields);
+                quote! {
+                    let entries = &mut ::nompose::convert::Entries::new(&node.children, node.line);
+                    #(#reads)*
+                    entries.finish()?;
+                    ::std::result::Result::Ok(#construct)
+                }
+            }
+        };
+
+        arms.push(quote!(#key => { #body }));
+    }
+
+    Ok(quote! {
+        fn from_tail(
+            tail: &[::nompose::Node],
+            line: usize,
+        ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+            match tail {
+                [node] => <Self as ::nompose::FromTermpose>::from_item(node),
+                [] => ::std::result::Result::Err(::nompose::DecodeError::Expected {
+                    what: "a variant",
+                    line,
+                }),
+                [_, extra, ..] => ::std::result::Result::Err(::nompose::DecodeError::Expected {
+                    what: "a single variant",
+                    line: extra.line,
+                }),
+            }
+        }
+
+        fn from_item(
+            node: &::nompose::Node,
+        ) -> ::std::result::Result<Self, ::nompose::DecodeError> {
+            let name = match node.name() {
+                ::std::option::Option::Some(name) => name,
+                ::std::option::Option::None => {
+                    return ::std::result::Result::Err(::nompose::DecodeError::Expected {
+                        what: "a variant name",
+                        line: node.line,
+                    })
+                }
+            };
+
+            match name {
+                #(#arms)*
+                _ => ::std::result::Result::Err(::nompose::DecodeError::UnknownVariant {
+                    name: name.into(),
+                    line: node.line,
+                }),
+            }
+        }
+    })
+}
+
+/// Read each field from `entries` into its binding
+fn reads(fields: &[Field]) -> Vec<TokenStream> {
+    fields::ordered(fields)
+        .map(|field| {
+            let Field {
+                binding, ty, key, ..
+            } = field;
+            let value = match (&field.attrs.default, field.attrs.positional) {
+                _ if field.attrs.flatten => {
+                    quote!(<#ty as ::nompose::FromTermpose>::from_entries(entries)?)
+                }
+                (attr::Default::None, true) => quote!(entries.positional::<#ty>(#key)?),
+                (attr::Default::None, false) => quote!(entries.key::<#ty>(#key)?),
+                (default, positional) => {
+                    let optional = if positional {
+                        quote!(entries.optional_positional::<#ty>()?)
+                    } else {
+                        quote!(entries.optional::<#ty>(#key)?)
+                    };
+                    let default = if let attr::Default::Path(path) = default {
+                        quote!(#path())
+                    } else {
+                        quote!(::std::default::Default::default())
+                    };
+                    quote! {
+                        match #optional {
+                            ::std::option::Option::Some(value) => value,
+                            ::std::option::Option::None => #default,
+                        }
+                    }
+                }
+            };
+
+            quote!(let #binding: #ty = #value;)
+        })
+        .collect()
+}
+
+/// Build the struct or variant from the bindings of its fields
+fn construct(path: &TokenStream, data: &Fields, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    let bindings = fields.iter().map(|field| &field.binding);
+    match data {
+        Fields::Named(_) => quote!(#path { #(#members: #bindings),* }),
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => quote!(#path),
+    }
+}
@@ -0,0 +1,59 @@
+//! Derive macros for nompose's `FromTermpose` and `ToTermpose`
+//!
+//! Use these through nompose itself, with its `derive` feature, which documents
+//! the attributes they take.
+
+#![forbid(unsafe_code)]
+#![deny(clippy::all, clippy::pedantic)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Generics};
+
+mod attr;
+mod fields;
+mod from;
+mod to;
+
+#[proc_macro_derive(FromTermpose, attributes(termpose))]
+pub fn derive_from_termpose(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from::expand(&input).unwrap_or_else(compile_errors).into()
+}
+
+#[proc_macro_derive(ToTermpose, attributes(termpose))]
+pub fn derive_to_termpose(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to::expand(&input).unwrap_or_else(compile_errors).into()
+}
+
+/// Report errors through `compile_error!`, which unlike the `::core` path syn
+/// writes, resolves within 2015 edition crates too
+fn compile_errors(error: syn::Error) -> proc_macro2::TokenStream {
+    error
+        .into_iter()
+        .map(|error| {
+            let message = error.to_string();
+            quote_spanned!(error.span()=> compile_error!(#message);)
+        })
+        .collect()
+}
+
+/// Require every type parameter to implement the trait being derived
+fn bounded(generics: &Generics, bound: &proc_macro2::TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
@@ -0,0 +1,152 @@
+//! `#[derive(ToTermpose)]`
+
+use attr::{self, Container};
+use fields::{self, Field, Shape};
+use proc_macro2::TokenStream;
+use syn::{Data, DataEnum, DeriveInput, Fields, Ident, Result};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::parse(input)?;
+    let name = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let head = container.rename.clone().unwrap_or_else(|| name.to_string());
+            structure(&data.fields, &container, &head)?
+        }
+        Data::Enum(data) => enumeration(data, &container)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "unions can't derive ToTermpose",
+            ))
+        }
+    };
+
+    let generics = ::bounded(&input.generics, &quote!(::nompose::ToTermpose));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::nompose::ToTermpose for #name #ty_generics #where_clause {
+            #body
+        }
+    })
+}
+
+fn structure(data: &Fields, container: &Container, head: &str) -> Result<TokenStream> {
+    if let Shape::Newtype = fields::shape(data)? {
+        return Ok(quote! {
+            fn to_tail(&self) -> ::std::vec::Vec<::nompose::Node<'static>> {
+                ::nompose::ToTermpose::to_tail(&self.0)
+            }
+
+            fn to_item(&self) -> ::nompose::Node<'static> {
+                ::nompose::ToTermpose::to_item(&self.0)
+            }
+
+            fn is_missing(&self) -> bool {
+                ::nompose::ToTermpose::is_missing(&self.0)
+            }
+        });
+    }
+
+    let fields = fields::parse(data, container.rename_all)?;
+    let bindings = fields.iter().map(|field| {
+        let Field {
+            binding, member, ..
+        } = field;
+        quote!(let #binding = &self.#member;)
+    });
+    let writes = writes(&fields);
+
+    Ok(quote! {
+        fn to_tail(&self) -> ::std::vec::Vec<::nompose::Node<'static>> {
+            #(#bindings)*
+            let mut tail = ::std::vec::Vec::new();
+            #(#writes)*
+            tail
+        }
+
+        fn to_item(&self) -> ::nompose::Node<'static> {
+            ::nompose::convert::keyed(#head, ::nompose::ToTermpose::to_tail(self))
+        }
+    })
+}
+
+fn enumeration(data: &DataEnum, container: &Container) -> Result<TokenStream> {
+    let mut arms = vec![];
+    for variant in &data.variants {
+        let attrs = attr::Variant::parse(&variant.attrs)?;
+        let key = attrs
+            .rename
+            .unwrap_or_else(|| container.rename_all.apply(&variant.ident));
+        let ident = &variant.ident;
+
+        arms.push(match fields::shape(&variant.fields)? {
+            Shape::Unit => quote! {
+                Self::#ident => ::nompose::convert::label(#key),
+            },
+            Shape::Newtype => quote! {
+                Self::#ident(value) => {
+                    ::nompose::convert::keyed(#key, ::nompose::ToTermpose::to_tail(value))
+                }
+            },
+            Shape::Fields => {
+                let fields = fields::parse(&variant.fields, container.rename_all)?;
+                let pattern = pattern(ident, &variant.fields, &fields);
+                let writes = writes(&fields);
+                quote! {
+                    #pattern => {
+                        let mut tail = ::std::vec::Vec::new();
+                        #(#writes)*
+                        ::nompose::convert::keyed(#key, tail)
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        fn to_tail(&self) -> ::std::vec::Vec<::nompose::Node<'static>> {
+            vec![::nompose::ToTermpose::to_item(self)]
+        }
+
+        fn to_item(&self) -> ::nompose::Node<'static> {
+            match self {
+                #(#arms)*
+            }
+        }
+    })
+}
+
+/// Push each field, from its binding, onto `tail`
+fn writes(fields: &[Field]) -> Vec<TokenStream> {
+    fields::ordered(fields)
+        .map(|field| {
+            let Field { binding, key, .. } = field;
+            if field.attrs.flatten {
+                quote!(tail.extend(::nompose::ToTermpose::to_tail(#binding));)
+            } else if field.attrs.positional {
+                quote!(tail.push(::nompose::ToTermpose::to_item(#binding));)
+            } else {
+                quote! {
+                    if !::nompose::ToTermpose::is_missing(#binding) {
+                        tail.push(::nompose::convert::keyed(
+                            #key,
+                            ::nompose::ToTermpose::to_tail(#binding),
+                        ));
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Match a variant, binding its fields
+fn pattern(ident: &Ident, data: &Fields, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    let bindings = fields.iter().map(|field| &field.binding);
+    match data {
+        Fields::Named(_) => quote!(Self::#ident { #(#members: #bindings),* }),
+        Fields::Unnamed(_) => quote!(Self::#ident(#(#bindings),*)),
+        Fields::Unit => quote!(Self::#ident),
+    }
+}
@@ -0,0 +1,31 @@
+use input;
+use nompose::parse_document;
+use report::diagnostic;
+use {usage, ERROR, FAILED, OK};
+
+/// Lex and parse files, reporting what's wrong with any that don't parse
+pub fn run<I: Iterator<Item = String>>(args: I) -> i32 {
+    let (flags, paths) = input::split(args);
+    if let Some(flag) = flags.first() {
+        return usage(&format!("unknown option `{flag}` for check"));
+    }
+
+    let mut status = OK;
+    for path in &paths {
+        let name = input::name(path);
+        match input::read(path) {
+            Ok(source) => {
+                if let Err(err) = parse_document(&source) {
+                    eprintln!("{}", diagnostic(name, &source, &err));
+                    status = status.max(FAILED);
+                }
+            }
+            Err(err) => {
+                eprintln!("error: {name}: {err}");
+                status = ERROR;
+            }
+        }
+    }
+
+    status
+}
@@ -0,0 +1,59 @@
+use input::{self, STDIN};
+use nompose::parse_document;
+use report::diagnostic;
+use std::fs;
+use {usage, ERROR, FAILED, OK};
+
+/// Rewrite files in canonical form
+///
+/// Files are rewritten in place, and stdin is written to stdout. With
+/// `--check`, nothing is written, and the status is FAILED if anything would
+/// have been changed.
+pub fn run<I: Iterator<Item = String>>(args: I) -> i32 {
+    let (flags, paths) = input::split(args);
+    let mut check = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--check" => check = true,
+            other => return usage(&format!("unknown option `{other}` for fmt")),
+        }
+    }
+
+    let mut status = OK;
+    for path in &paths {
+        let name = input::name(path);
+        let source = match input::read(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: {name}: {err}");
+                status = ERROR;
+                continue;
+            }
+        };
+
+        let formatted = match parse_document(&source) {
+            Ok(doc) => doc.to_string(),
+            Err(err) => {
+                eprintln!("{}", diagnostic(name, &source, &err));
+                status = ERROR;
+                continue;
+            }
+        };
+
+        if check {
+            if formatted != source {
+                eprintln!("{name}: not formatted");
+                status = status.max(FAILED);
+            }
+        } else if path == STDIN {
+            print!("{formatted}");
+        } else if formatted != source {
+            if let Err(err) = fs::write(path, formatted) {
+                eprintln!("error: {name}: {err}");
+                status = ERROR;
+            }
+        }
+    }
+
+    status
+}
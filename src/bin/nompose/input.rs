@@ -0,0 +1,36 @@
+use std::fs;
+use std::io::{self, Read};
+
+/// Stands for stdin among file arguments
+pub const STDIN: &str = "-";
+
+/// Read a file, or stdin for `-`
+pub fn read(path: &str) -> io::Result<String> {
+    if path == STDIN {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        Ok(source)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+/// How to refer to a file argument in messages
+pub fn name(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+/// Split arguments into flags and files, defaulting to stdin if there are no files
+pub fn split<I: Iterator<Item = String>>(args: I) -> (Vec<String>, Vec<String>) {
+    let (flags, mut paths): (Vec<String>, Vec<String>) =
+        args.partition(|arg| arg.starts_with("--"));
+    if paths.is_empty() {
+        paths.push(STDIN.into());
+    }
+
+    (flags, paths)
+}
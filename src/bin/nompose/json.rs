@@ -0,0 +1,81 @@
+use input;
+use nompose::json::{from_json, to_json, Mapping};
+use nompose::{parse_document, write};
+use report::diagnostic;
+use serde_json::{self, Value};
+use {usage, ERROR, OK};
+
+/// Convert termpose files to JSON, printing each as a JSON value of its own
+pub fn to<I: Iterator<Item = String>>(args: I) -> i32 {
+    let Some((mapping, paths)) = options(args, "to-json") else {
+        return ERROR;
+    };
+
+    convert(&paths, |name, source| match parse_document(source) {
+        Ok(doc) => {
+            let json = to_json(&doc.children, mapping);
+            serde_json::to_string_pretty(&json)
+                .map(|json| json + "\n")
+                .map_err(|err| format!("error: {name}: {err}"))
+        }
+        Err(err) => Err(diagnostic(name, source, &err)),
+    })
+}
+
+/// Convert JSON files to canonical termpose
+pub fn from<I: Iterator<Item = String>>(args: I) -> i32 {
+    let Some((mapping, paths)) = options(args, "from-json") else {
+        return ERROR;
+    };
+
+    convert(&paths, |name, source| {
+        let json: Value =
+            serde_json::from_str(source).map_err(|err| format!("error: {name}: {err}"))?;
+        let nodes = from_json(&json, mapping).map_err(|err| format!("error: {name}: {err}"))?;
+
+        let mut out = String::new();
+        write(&mut out, &nodes).map_err(|err| format!("error: {name}: {err}"))?;
+        Ok(out)
+    })
+}
+
+/// The mapping and files asked for, or None after complaining about the options
+fn options<I: Iterator<Item = String>>(args: I, command: &str) -> Option<(Mapping, Vec<String>)> {
+    let (flags, paths) = input::split(args);
+    let mut mapping = Mapping::HeadTail;
+    for flag in &flags {
+        match flag.as_str() {
+            "--natural" => mapping = Mapping::Natural,
+            other => {
+                usage(&format!("unknown option `{other}` for {command}"));
+                return None;
+            }
+        }
+    }
+
+    Some((mapping, paths))
+}
+
+/// Read each file, convert it, and print the result or what went wrong
+fn convert<F>(paths: &[String], convert: F) -> i32
+where
+    F: Fn(&str, &str) -> Result<String, String>,
+{
+    let mut status = OK;
+    for path in paths {
+        let name = input::name(path);
+        let converted = input::read(path)
+            .map_err(|err| format!("error: {name}: {err}"))
+            .and_then(|source| convert(name, &source));
+
+        match converted {
+            Ok(out) => print!("{out}"),
+            Err(err) => {
+                eprintln!("{err}");
+                status = ERROR;
+            }
+        }
+    }
+
+    status
+}
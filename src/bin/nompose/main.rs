@@ -0,0 +1,67 @@
+extern crate nompose;
+#[cfg(feature = "json")]
+extern crate serde_json;
+
+use std::env;
+use std::process;
+
+mod check;
+mod fmt;
+mod input;
+#[cfg(feature = "json")]
+mod json;
+mod query;
+mod report;
+
+const USAGE: &str = "usage: nompose <command> [options] [FILE...]
+
+Files are read from stdin when none are given, or for `-`.
+
+commands:
+    check            report where any files fail to parse
+    fmt [--check]    rewrite files in canonical form, or with --check, fail if
+                     any aren't already
+    query PATH       print the nodes matching PATH, such as `mon/*/drain`,
+                     failing if there are none
+    to-json [--natural]
+                     convert files to JSON, as heads and tails, or with
+                     --natural, with `key value` lines as objects
+    from-json [--natural]
+                     convert JSON files to termpose";
+
+/// Exit statuses
+const OK: i32 = 0;
+const FAILED: i32 = 1;
+const ERROR: i32 = 2;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let status = match args.next().as_deref() {
+        Some("check") => check::run(args),
+        Some("fmt") => fmt::run(args),
+        Some("query") => query::run(args),
+        #[cfg(feature = "json")]
+        Some("to-json") => json::to(args),
+        #[cfg(feature = "json")]
+        Some("from-json") => json::from(args),
+        #[cfg(not(feature = "json"))]
+        Some(command @ ("to-json" | "from-json")) => {
+            eprintln!("error: `{command}` needs nompose built with the `json` feature");
+            ERROR
+        }
+        Some("-h" | "--help" | "help") => {
+            println!("{USAGE}");
+            OK
+        }
+        Some(other) => usage(&format!("unknown command `{other}`")),
+        None => usage("no command given"),
+    };
+
+    process::exit(status);
+}
+
+/// Complain about how the program was called
+fn usage(problem: &str) -> i32 {
+    eprintln!("error: {problem}\n\n{USAGE}");
+    ERROR
+}
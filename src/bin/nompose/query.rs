@@ -0,0 +1,55 @@
+use input;
+use nompose::{parse_document, Query};
+use report::diagnostic;
+use {usage, ERROR, FAILED, OK};
+
+/// Print the nodes in files which match a query, in canonical form
+///
+/// The status is FAILED if nothing matches.
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> i32 {
+    let Some(path) = args.next() else {
+        return usage("query needs a path to look for");
+    };
+    let (flags, paths) = input::split(args);
+    if let Some(flag) = flags.first() {
+        return usage(&format!("unknown option `{flag}` for query"));
+    }
+
+    let query: Query = match path.parse() {
+        Ok(query) => query,
+        Err(err) => {
+            eprintln!("error: query `{path}`: {err}");
+            return ERROR;
+        }
+    };
+
+    let mut status = FAILED;
+    for path in &paths {
+        let name = input::name(path);
+        let source = match input::read(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: {name}: {err}");
+                status = ERROR;
+                continue;
+            }
+        };
+
+        match parse_document(&source) {
+            Ok(doc) => {
+                for node in query.find(&doc.children) {
+                    print!("{node}");
+                    if status == FAILED {
+                        status = OK;
+                    }
+                }
+            }
+            Err(err) => {
+                eprintln!("{}", diagnostic(name, &source, &err));
+                status = ERROR;
+            }
+        }
+    }
+
+    status
+}
@@ -0,0 +1,42 @@
+use nompose::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// Describe an error in a file, quoting the line it's on with a caret under it
+///
+/// ```text
+/// error: unbalanced close paren
+///  --> bad.tp:2:4
+///   |
+/// 2 |   b)
+///   |    ^
+/// ```
+pub fn diagnostic(name: &str, source: &str, err: &Error) -> String {
+    let position = err.position();
+    let offset = position.offset.min(source.len());
+    let (before, after) = source.split_at(offset);
+
+    let start = before.rfind(['\r', '\n']).map_or(0, |i| i + 1);
+    let end = after
+        .find(['\r', '\n'])
+        .map_or(source.len(), |i| offset + i);
+    let line = source.get(start..end).unwrap_or_default();
+
+    // Keep tabs, so the caret lines up however wide they're shown
+    let pad: String = source
+        .get(start..offset)
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let number = position.line.to_string();
+    let gutter = " ".repeat(number.len());
+    format!(
+        "error: {}\n{gutter}--> {name}:{}:{}\n{gutter} |\n{number} | {line}\n{gutter} | {pad}^\n",
+        err.summary(),
+        position.line,
+        position.column,
+    )
+}
@@ -0,0 +1,59 @@
+use super::*;
+use nompose::parse;
+
+fn report(source: &str) -> String {
+    diagnostic("test.tp", source, &parse(source).unwrap_err())
+}
+
+#[test]
+fn caret_under_the_problem() {
+    assert_eq!(
+        report("a\n  b)\n"),
+        "error: unbalanced close paren
+ --> test.tp:2:4
+  |
+2 |   b)
+  |    ^
+"
+    );
+}
+
+#[test]
+fn wrong_indent_at_the_same_level() {
+    assert_eq!(
+        report("a\n  b\n\t\tc\n"),
+        "error: inconsistent indent
+ --> test.tp:3:3
+  |
+3 | \t\tc
+  | \t\t^
+"
+    );
+}
+
+#[test]
+fn wide_line_numbers_and_crlf() {
+    let source = format!("{}a\r\nb)\r\n", "x\r\n".repeat(9));
+    assert_eq!(
+        report(&source),
+        "error: unbalanced close paren
+  --> test.tp:11:2
+   |
+11 | b)
+   |  ^
+"
+    );
+}
+
+#[test]
+fn error_at_the_end() {
+    assert_eq!(
+        report("a \\"),
+        "error: invalid escape
+ --> test.tp:1:3
+  |
+1 | a \\
+  |   ^
+"
+    );
+}
@@ -0,0 +1,646 @@
+//! Typed decoding and encoding of trees, without serde
+//!
+//! Values are read from and written as the tail of the node they belong to,
+//! the same way [`de`](crate::de) and [`ser`](crate::ser) lay them out: a struct
+//! field `health: u32` is `health 50`. Within a sequence, each item is a single
+//! node, so a struct is headed by its name, and a sequence is an s-list.
+//!
+//! Structs and enums can derive both traits with the `derive` feature:
+//!
+#![cfg_attr(
+    feature = "derive",
+    doc = r#"
+```
+# extern crate nompose;
+use nompose::{FromTermpose, ToTermpose};
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Mon {
+    name: String,
+    #[termpose(rename = "hp")]
+    health: u32,
+    #[termpose(default)]
+    tags: Vec<String>,
+}
+
+# fn main() {
+let nodes = nompose::parse("name leafward\nhp 50\n").unwrap();
+let mon = Mon::from_tail(&nodes, 0).unwrap();
+assert_eq!(mon.health, 50);
+assert!(mon.tags.is_empty());
+assert_eq!(nompose::write_value(&mon), "name leafward\nhp 50\ntags\n");
+# }
+```
+"#
+)]
+//!
+//! Fields take these attributes:
+//!
+//!  - `rename = "key"`: read and write the field with another key;
+//!  - `default`, or `default = "path"`: if the key is missing, use
+//!    `Default::default()`, or call the function at the path;
+//!  - `flatten`: read the fields of a struct from the same tail, as if they
+//!    were fields of this one;
+//!  - `positional`: read the field from the next item at the front of the tail,
+//!    before any keys, like the `x` and `y` in `point 3 4`.
+//!
+//! A single unnamed field, as in `struct Health(u32)`, stands in for the
+//! whole, so takes none of these.
+//!
+//! Structs and variants can be `rename = "name"`d, which changes the head of a
+//! struct written as an item, or the name of a variant; enums are written as
+//! their variants, so can't be. Structs and enums can take
+//! `rename_all = "lowercase"`, `"snake_case"` or `"kebab-case"` for their
+//! fields or variants.
+//!
+//! Keys that are missing, repeated, or not known by the type are reported with
+//! the line of the node they're on, or belong under.
+
+use node::Node;
+use printer::Style;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error as StdError;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::slice;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+/// What can go wrong decoding a tree into a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A key needed by the type wasn't given, under the node at `line`
+    MissingKey { key: String, line: usize },
+
+    /// A key was given more than once
+    DuplicateKey { key: String, line: usize },
+
+    /// A key, or item, wasn't expected by the type
+    UnknownKey { key: String, line: usize },
+
+    /// An enum has no variant by this name
+    UnknownVariant { name: String, line: usize },
+
+    /// The tree isn't the right shape for the type
+    Expected { what: &'static str, line: usize },
+
+    /// A label couldn't be read as the type
+    Invalid {
+        label: String,
+        reason: String,
+        line: usize,
+    },
+}
+
+impl DecodeError {
+    /// The line the problem was found on
+    pub fn line(&self) -> usize {
+        match *self {
+            DecodeError::MissingKey { line, .. }
+            | DecodeError::DuplicateKey { line, .. }
+            | DecodeError::UnknownKey { line, .. }
+            | DecodeError::UnknownVariant { line, .. }
+            | DecodeError::Expected { line, .. }
+            | DecodeError::Invalid { line, .. } => line,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::MissingKey { key, line } => {
+                write!(f, "missing key `{key}` under line {line}")
+            }
+            DecodeError::DuplicateKey { key, line } => {
+                write!(f, "duplicate key `{key}` at line {line}")
+            }
+            DecodeError::UnknownKey { key, line } => {
+                write!(f, "unknown key `{key}` at line {line}")
+            }
+            DecodeError::UnknownVariant { name, line } => {
+                write!(f, "unknown variant `{name}` at line {line}")
+            }
+            DecodeError::Expected { what, line } => write!(f, "expected {what} at line {line}"),
+            DecodeError::Invalid {
+                label,
+                reason,
+                line,
+            } => write!(f, "invalid label `{label}` at line {line}: {reason}"),
+        }
+    }
+}
+
+impl StdError for DecodeError {}
+
+/// Types which can be decoded from a tree
+pub trait FromTermpose: Sized {
+    /// Decode a value from the tail of the node at `line`
+    ///
+    /// For the top-level nodes of a document, `line` is 0.
+    ///
+    /// # Errors
+    ///
+    /// If the tail isn't the right shape for the type.
+    fn from_tail(tail: &[Node], line: usize) -> Result<Self, DecodeError>;
+
+    /// Decode a value from a single item of a tail
+    ///
+    /// By default, the item is read as a tail of one.
+    ///
+    /// # Errors
+    ///
+    /// If the item isn't the right shape for the type.
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        Self::from_tail(slice::from_ref(node), node.line)
+    }
+
+    /// Decode a struct from keys and positional items shared with others
+    ///
+    /// Only structs can be flattened into others, and implement this.
+    ///
+    /// # Errors
+    ///
+    /// If the entries don't fit the type, or it isn't a struct.
+    fn from_entries(entries: &mut Entries) -> Result<Self, DecodeError> {
+        Err(DecodeError::Expected {
+            what: "a struct to flatten",
+            line: entries.line(),
+        })
+    }
+
+    /// The value to use when the key or item is missing altogether
+    ///
+    /// By default, that's an error, but `Option` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// If the value can't be missing.
+    fn from_missing(key: &str, line: usize) -> Result<Self, DecodeError> {
+        Err(DecodeError::MissingKey {
+            key: key.into(),
+            line,
+        })
+    }
+}
+
+/// Types which can be encoded as a tree
+pub trait ToTermpose {
+    /// Encode the value as the tail of a node
+    fn to_tail(&self) -> Vec<Node<'static>>;
+
+    /// Encode the value as a single item of a tail
+    ///
+    /// By default, a tail of one is that item, and any other tail an s-list
+    /// of its nodes.
+    fn to_item(&self) -> Node<'static> {
+        list(self.to_tail())
+    }
+
+    /// Whether to leave out the key or item altogether, as for `None`
+    fn is_missing(&self) -> bool {
+        false
+    }
+}
+
+/// Write a value out as a termpose document
+///
+/// Nodes with nothing but labels in their tails are written on one line, and
+/// everything else indented, as with [`Style::shallow`].
+pub fn write_value<T: ToTermpose + ?Sized>(value: &T) -> String {
+    Style::shallow(80).print(&value.to_tail())
+}
+
+/// A lone label node
+pub fn label<N: Into<String>>(name: N) -> Node<'static> {
+    Node::new(name.into(), "", 0)
+}
+
+/// A node with a label head, and a tail
+pub fn keyed<N: Into<String>>(name: N, tail: Vec<Node<'static>>) -> Node<'static> {
+    let mut node = label(name);
+    node.children = tail;
+    node
+}
+
+/// An s-list of nodes: the first is its head, and the rest its tail
+///
+/// No nodes at all are nil, and one node is itself.
+pub fn list(nodes: Vec<Node<'static>>) -> Node<'static> {
+    let mut nodes = nodes.into_iter();
+    let mut node = match nodes.next() {
+        None => return Node::nil("", 0),
+        Some(first) if first.is_label() => first,
+        Some(first) if nodes.len() == 0 => return first,
+        Some(first) => Node::list(first, "", 0),
+    };
+
+    node.children.extend(nodes);
+    node
+}
+
+/// The keys and positional items of a tail, as they're decoded
+///
+/// Derived [`FromTermpose`] impls take their fields from these, then check
+/// that nothing unknown is left over with [`Entries::finish`].
+pub struct Entries<'a, 'n: 'a> {
+    nodes: &'a [Node<'n>],
+    line: usize,
+    taken: Vec<bool>,
+}
+
+impl<'a, 'n> Entries<'a, 'n> {
+    /// The entries of the tail of the node at `line`
+    pub fn new(tail: &'a [Node<'n>], line: usize) -> Self {
+        Self {
+            nodes: tail,
+            line,
+            taken: vec![false; tail.len()],
+        }
+    }
+
+    /// The line of the node the tail belongs to
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Take the next item not already taken, from the front
+    ///
+    /// # Errors
+    ///
+    /// If there are no items left and `T` can't be missing, or the item
+    /// doesn't decode as `T`.
+    pub fn positional<T: FromTermpose>(&mut self, name: &str) -> Result<T, DecodeError> {
+        match self.optional_positional()? {
+            Some(value) => Ok(value),
+            None => T::from_missing(name, self.line),
+        }
+    }
+
+    /// Take the next item not already taken, if there is one
+    ///
+    /// # Errors
+    ///
+    /// If the item doesn't decode as `T`.
+    pub fn optional_positional<T: FromTermpose>(&mut self) -> Result<Option<T>, DecodeError> {
+        let Some(i) = self.taken.iter().position(|taken| !taken) else {
+            return Ok(None);
+        };
+
+        self.taken[i] = true;
+        T::from_item(&self.nodes[i]).map(Some)
+    }
+
+    /// Take the value of a key, if it's there
+    ///
+    /// # Errors
+    ///
+    /// If the key is given more than once, or its value doesn't decode as `T`.
+    pub fn optional<T: FromTermpose>(&mut self, key: &str) -> Result<Option<T>, DecodeError> {
+        let mut found = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            if self.taken[i] || node.name() != Some(key) {
+                continue;
+            }
+
+            if found.is_some() {
+                return Err(DecodeError::DuplicateKey {
+                    key: key.into(),
+                    line: node.line,
+                });
+            }
+
+            self.taken[i] = true;
+            found = Some(node);
+        }
+
+        found
+            .map(|node| T::from_tail(&node.children, node.line))
+            .transpose()
+    }
+
+    /// Take the value of a key
+    ///
+    /// # Errors
+    ///
+    /// If the key is missing and `T` can't be, is given more than once, or
+    /// its value doesn't decode as `T`.
+    pub fn key<T: FromTermpose>(&mut self, key: &str) -> Result<T, DecodeError> {
+        match self.optional(key)? {
+            Some(value) => Ok(value),
+            None => T::from_missing(key, self.line),
+        }
+    }
+
+    /// Check that every node has been taken
+    ///
+    /// # Errors
+    ///
+    /// For the first node left, as an unknown key.
+    pub fn finish(&self) -> Result<(), DecodeError> {
+        match self.taken.iter().position(|taken| !taken) {
+            Some(i) => {
+                let node = &self.nodes[i];
+                let key = match node.name() {
+                    Some(name) => name.into(),
+                    None => Style::inline()
+                        .print(slice::from_ref(node))
+                        .trim_end()
+                        .into(),
+                };
+                Err(DecodeError::UnknownKey {
+                    key,
+                    line: node.line,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The single label in a tail
+pub(crate) fn single<'a>(tail: &'a [Node], line: usize) -> Result<&'a Node<'a>, DecodeError> {
+    match tail {
+        [node] if node.is_label() => Ok(node),
+        [node, ..] => Err(DecodeError::Expected {
+            what: "a single label",
+            line: node.line,
+        }),
+        [] => Err(DecodeError::Expected {
+            what: "a label",
+            line,
+        }),
+    }
+}
+
+/// Parse the single label in a tail
+pub(crate) fn parsed<T: FromStr>(tail: &[Node], line: usize) -> Result<T, DecodeError>
+where
+    T::Err: fmt::Display,
+{
+    let node = single(tail, line)?;
+    let name = node.name().unwrap_or_default();
+    name.parse().map_err(|err: T::Err| DecodeError::Invalid {
+        label: name.into(),
+        reason: err.to_string(),
+        line: node.line,
+    })
+}
+
+macro_rules! labels {
+    ($($type:ty)*) => {
+        $(
+            impl FromTermpose for $type {
+                fn from_tail(tail: &[Node], line: usize) -> Result<Self, DecodeError> {
+                    parsed(tail, line)
+                }
+            }
+
+            impl ToTermpose for $type {
+                fn to_tail(&self) -> Vec<Node<'static>> {
+                    vec![label(self.to_string())]
+                }
+            }
+        )*
+    };
+}
+
+labels! {
+    bool char String
+    i8 i16 i32 i64 i128 isize
+    u8 u16 u32 u64 u128 usize
+    f32 f64
+}
+
+impl ToTermpose for str {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        vec![label(self)]
+    }
+}
+
+impl<T: ToTermpose + ?Sized> ToTermpose for &T {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        (**self).to_tail()
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        (**self).to_item()
+    }
+
+    fn is_missing(&self) -> bool {
+        (**self).is_missing()
+    }
+}
+
+impl<T: FromTermpose> FromTermpose for Box<T> {
+    fn from_tail(tail: &[Node], line: usize) -> Result<Self, DecodeError> {
+        T::from_tail(tail, line).map(Box::new)
+    }
+
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        T::from_item(node).map(Box::new)
+    }
+
+    fn from_entries(entries: &mut Entries) -> Result<Self, DecodeError> {
+        T::from_entries(entries).map(Box::new)
+    }
+
+    fn from_missing(key: &str, line: usize) -> Result<Self, DecodeError> {
+        T::from_missing(key, line).map(Box::new)
+    }
+}
+
+impl<T: ToTermpose + ?Sized> ToTermpose for Box<T> {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        (**self).to_tail()
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        (**self).to_item()
+    }
+
+    fn is_missing(&self) -> bool {
+        (**self).is_missing()
+    }
+}
+
+/// An empty tail is `None`, as is a missing key or nil item
+impl<T: FromTermpose> FromTermpose for Option<T> {
+    fn from_tail(tail: &[Node], line: usize) -> Result<Self, DecodeError> {
+        if tail.is_empty() {
+            Ok(None)
+        } else {
+            T::from_tail(tail, line).map(Some)
+        }
+    }
+
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        if node.is_nil() {
+            Ok(None)
+        } else {
+            T::from_item(node).map(Some)
+        }
+    }
+
+    fn from_missing(_key: &str, _line: usize) -> Result<Self, DecodeError> {
+        Ok(None)
+    }
+}
+
+impl<T: ToTermpose> ToTermpose for Option<T> {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        self.as_ref().map(T::to_tail).unwrap_or_default()
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        match self {
+            Some(value) => value.to_item(),
+            None => Node::nil("", 0),
+        }
+    }
+
+    fn is_missing(&self) -> bool {
+        self.is_none()
+    }
+}
+
+/// A tail of items, or within an item, an s-list of them
+impl<T: FromTermpose> FromTermpose for Vec<T> {
+    fn from_tail(tail: &[Node], _line: usize) -> Result<Self, DecodeError> {
+        tail.iter().map(T::from_item).collect()
+    }
+
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        let head = match (node.name(), node.head_list()) {
+            (Some(name), _) => Some(T::from_item(&Node::new(name, "", node.line))),
+            (None, Some(list)) => Some(T::from_item(list)),
+            (None, None) => None,
+        };
+
+        head.into_iter()
+            .chain(node.children.iter().map(T::from_item))
+            .collect()
+    }
+}
+
+impl<T: ToTermpose> ToTermpose for Vec<T> {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        self[..].to_tail()
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        self[..].to_item()
+    }
+}
+
+impl<T: ToTermpose> ToTermpose for [T] {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        self.iter().map(ToTermpose::to_item).collect()
+    }
+}
+
+/// Nodes headed by their keys, with the values as their tails
+impl<K: FromStr + Ord, V: FromTermpose> FromTermpose for BTreeMap<K, V>
+where
+    K::Err: fmt::Display,
+{
+    fn from_tail(tail: &[Node], _line: usize) -> Result<Self, DecodeError> {
+        entries(tail, Self::insert)
+    }
+
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        Self::from_tail(&node.children, node.line)
+    }
+}
+
+impl<K: fmt::Display, V: ToTermpose> ToTermpose for BTreeMap<K, V> {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        to_entries(self)
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        map_item(self.to_tail())
+    }
+}
+
+impl<K, V, S> FromTermpose for HashMap<K, V, S>
+where
+    K: FromStr + Eq + Hash,
+    K::Err: fmt::Display,
+    V: FromTermpose,
+    S: BuildHasher + Default,
+{
+    fn from_tail(tail: &[Node], _line: usize) -> Result<Self, DecodeError> {
+        entries(tail, Self::insert)
+    }
+
+    fn from_item(node: &Node) -> Result<Self, DecodeError> {
+        Self::from_tail(&node.children, node.line)
+    }
+}
+
+impl<K: fmt::Display, V: ToTermpose, S> ToTermpose for HashMap<K, V, S> {
+    fn to_tail(&self) -> Vec<Node<'static>> {
+        to_entries(self)
+    }
+
+    fn to_item(&self) -> Node<'static> {
+        map_item(self.to_tail())
+    }
+}
+
+/// Decode the keys and values of a map, putting each in with `insert`, which
+/// gives back any value already under the key
+fn entries<K, V, M, F>(tail: &[Node], mut insert: F) -> Result<M, DecodeError>
+where
+    K: FromStr,
+    K::Err: fmt::Display,
+    V: FromTermpose,
+    M: Default,
+    F: FnMut(&mut M, K, V) -> Option<V>,
+{
+    let mut map = M::default();
+    for node in tail {
+        let Some(name) = node.name() else {
+            return Err(DecodeError::Expected {
+                what: "a label for a key",
+                line: node.line,
+            });
+        };
+
+        let key = name.parse().map_err(|err: K::Err| DecodeError::Invalid {
+            label: name.into(),
+            reason: err.to_string(),
+            line: node.line,
+        })?;
+        let value = V::from_tail(&node.children, node.line)?;
+        if insert(&mut map, key, value).is_some() {
+            return Err(DecodeError::DuplicateKey {
+                key: name.into(),
+                line: node.line,
+            });
+        }
+    }
+    Ok(map)
+}
+
+fn to_entries<'a, K, V, I>(map: I) -> Vec<Node<'static>>
+where
+    K: fmt::Display + 'a,
+    V: ToTermpose + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    map.into_iter()
+        .filter(|(_, value)| !value.is_missing())
+        .map(|(key, value)| keyed(key.to_string(), value.to_tail()))
+        .collect()
+}
+
+/// A map within a sequence, as a nil-headed node of entries
+fn map_item(entries: Vec<Node<'static>>) -> Node<'static> {
+    let mut node = Node::nil("", 0);
+    node.children = entries;
+    node
+}
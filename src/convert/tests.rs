@@ -0,0 +1,122 @@
+use super::*;
+use parse;
+
+fn decode<T: FromTermpose>(input: &str) -> Result<T, DecodeError> {
+    T::from_tail(&parse(input).unwrap(), 0)
+}
+
+#[test]
+fn labels() {
+    assert_eq!(decode::<u8>("7\n"), Ok(7));
+    assert_eq!(decode::<bool>("true\n"), Ok(true));
+    assert_eq!(decode::<String>("\"a b\"\n"), Ok("a b".into()));
+    assert_eq!(
+        decode::<u8>("\n300\n"),
+        Err(DecodeError::Invalid {
+            label: "300".into(),
+            reason: "number too large to fit in target type".into(),
+            line: 2,
+        })
+    );
+    assert_eq!(
+        decode::<u8>("1 2\n"),
+        Err(DecodeError::Expected {
+            what: "a single label",
+            line: 1,
+        })
+    );
+    assert_eq!(
+        decode::<u8>(""),
+        Err(DecodeError::Expected {
+            what: "a label",
+            line: 0,
+        })
+    );
+}
+
+#[test]
+fn sequences_and_options() {
+    assert_eq!(decode::<Vec<u8>>("1\n2\n"), Ok(vec![1, 2]));
+    assert_eq!(
+        decode::<Vec<Vec<u8>>>("1 2\n3\n"),
+        Ok(vec![vec![1, 2], vec![3]])
+    );
+    assert_eq!(decode::<Option<u8>>(""), Ok(None));
+    assert_eq!(
+        decode::<Vec<Option<u8>>>("1\n()\n"),
+        Ok(vec![Some(1), None])
+    );
+
+    assert_eq!(write_value(&vec![vec![1, 2], vec![3]]), "1 2\n3\n");
+    assert_eq!(write_value(&vec![Some(1), None]), "1\n()\n");
+}
+
+#[test]
+fn maps() {
+    let map: BTreeMap<String, Vec<u8>> = decode("a 1 2\nb 3\n").unwrap();
+    assert_eq!(map["a"], [1, 2]);
+    assert_eq!(write_value(&map), "a 1 2\nb 3\n");
+
+    assert_eq!(
+        decode::<BTreeMap<u8, u8>>("x 1\n"),
+        Err(DecodeError::Invalid {
+            label: "x".into(),
+            reason: "invalid digit found in string".into(),
+            line: 1,
+        })
+    );
+
+    let duplicate = DecodeError::DuplicateKey {
+        key: "01".into(),
+        line: 3,
+    };
+    let source = "1 2\n2 3\n01 4\n";
+    assert_eq!(decode::<BTreeMap<u8, u8>>(source).unwrap_err(), duplicate);
+    assert_eq!(decode::<HashMap<u8, u8>>(source).unwrap_err(), duplicate);
+    assert_eq!(decode::<BTreeMap<String, u8>>(source).unwrap()["01"], 4);
+}
+
+#[test]
+fn entries() {
+    let nodes = parse("point 3 4\n    colour red\n    colour blue\n").unwrap();
+    let point = &nodes[0];
+
+    let mut entries = Entries::new(&point.children, point.line);
+    assert_eq!(entries.positional::<i32>("x"), Ok(3));
+    assert_eq!(entries.optional_positional::<i32>(), Ok(Some(4)));
+    assert_eq!(
+        entries.key::<String>("colour"),
+        Err(DecodeError::DuplicateKey {
+            key: "colour".into(),
+            line: 3,
+        })
+    );
+
+    let mut entries = Entries::new(&point.children, point.line);
+    assert_eq!(
+        entries.key::<u8>("size"),
+        Err(DecodeError::MissingKey {
+            key: "size".into(),
+            line: 1,
+        })
+    );
+    assert_eq!(entries.optional::<u8>("size"), Ok(None));
+    assert_eq!(entries.key::<Option<u8>>("size"), Ok(None));
+    assert_eq!(
+        entries.finish(),
+        Err(DecodeError::UnknownKey {
+            key: "3".into(),
+            line: 1,
+        })
+    );
+}
+
+#[test]
+fn lists() {
+    assert!(list(vec![]).is_nil());
+    assert_eq!(list(vec![label("a")]), label("a"));
+    assert_eq!(
+        Style::inline().print(&[list(vec![list(vec![label("a"), label("b")]), label("c")])]),
+        "a(b) c\n"
+    );
+}
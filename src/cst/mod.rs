@@ -0,0 +1,255 @@
+//! Lossless concrete syntax tree
+//!
+//! Where the [lexer](crate::lexer) keeps only what the parser needs, this keeps every
+//! byte of the source: exact spacing, line endings, and how each label was
+//! written. Writing a [`Cst`] back out gives the source it came from.
+
+use error::Error;
+use lexer::{lex_located, Close, Lexeme, Open, Token};
+use position::{Locator, Span};
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// How a label was written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quoting {
+    /// As is, with no escapes
+    Bare,
+
+    /// Bare, but with escapes
+    Escaped,
+
+    /// In double quotes
+    Quoted,
+
+    /// As part of a multiline label: the text after its opening quote, or one
+    /// of the lines below
+    Multiline,
+}
+
+/// What a piece of the source is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Kind<'a> {
+    /// The whitespace at the start of a line
+    Indent,
+
+    /// A label, with its value once any escapes are resolved
+    Label(Cow<'a, str>, Quoting),
+
+    Open(Open),
+    Close(Close),
+
+    /// Spaces and tabs within a line
+    Space,
+
+    /// A line ending: `\n`, `\r\n` or `\r`
+    Newline,
+}
+
+/// A piece of the source, exactly as it was written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Piece<'a> {
+    pub kind: Kind<'a>,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// A line of the source, including its line ending if it has one
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub pieces: Vec<Piece<'a>>,
+}
+
+impl<'a> Line<'a> {
+    /// The whitespace at the start of the line
+    pub fn indent(&self) -> &'a str {
+        match self.pieces.first() {
+            Some(Piece {
+                kind: Kind::Indent,
+                text,
+                ..
+            }) => text,
+            _ => "",
+        }
+    }
+
+    /// Whether the line has nothing on it but whitespace
+    pub fn is_blank(&self) -> bool {
+        self.pieces
+            .iter()
+            .all(|piece| matches!(piece.kind, Kind::Indent | Kind::Space | Kind::Newline))
+    }
+}
+
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.pieces
+            .iter()
+            .try_for_each(|piece| f.write_str(piece.text))
+    }
+}
+
+/// A whole termpose document, as lines of pieces
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cst<'a> {
+    pub source: &'a str,
+    pub lines: Vec<Line<'a>>,
+}
+
+impl<'a> Cst<'a> {
+    /// Lex a document, keeping everything
+    ///
+    /// # Errors
+    ///
+    /// If the source contains something that isn't valid termpose.
+    pub fn parse(source: &'a str) -> Result<Self, Error> {
+        let mut builder = Builder {
+            source,
+            locator: Locator::new(source),
+            cursor: 0,
+            lines: vec![],
+            line: Line::default(),
+            opener: None,
+        };
+
+        for (_, line) in lex_located(source)? {
+            builder.line(&line.0);
+        }
+
+        Ok(builder.finish())
+    }
+
+    /// The pieces of every line, in order
+    pub fn pieces(&self) -> impl Iterator<Item = &Piece<'a>> {
+        self.lines.iter().flat_map(|line| line.pieces.iter())
+    }
+}
+
+impl fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.lines.iter().try_for_each(|line| line.fmt(f))
+    }
+}
+
+/// Puts lexemes and the text between them together into lines
+struct Builder<'a> {
+    source: &'a str,
+    locator: Locator<'a>,
+
+    /// How far into the source the pieces so far reach
+    cursor: usize,
+
+    lines: Vec<Line<'a>>,
+    line: Line<'a>,
+
+    /// The indent of the line which opened the multiline label being read, if any
+    opener: Option<&'a str>,
+}
+
+impl<'a> Builder<'a> {
+    /// Add a lexed line, and the text before it
+    fn line(&mut self, lexemes: &[Lexeme<'a>]) {
+        let indent = match lexemes.first() {
+            Some(Lexeme {
+                token: Token::Indent(_),
+                span,
+            }) => self.text(span),
+            _ => "",
+        };
+
+        // The same rule the lexer and parser use for multiline labels
+        let blank = lexemes.iter().all(|l| matches!(l.token, Token::Indent(_)));
+        let quoted = self.opener.is_some_and(|opener| {
+            blank || (indent.len() > opener.len() && indent.starts_with(opener))
+        });
+        if !quoted {
+            self.opener = None;
+        }
+
+        let mut after_quote = false;
+        for lexeme in lexemes {
+            self.gap(lexeme.span.start.offset);
+            let kind = match &lexeme.token {
+                Token::Indent(_) => Kind::Indent,
+                Token::Tag(tag) => {
+                    let text = self.text(&lexeme.span);
+                    let quoting = if quoted || after_quote {
+                        Quoting::Multiline
+                    } else if text.starts_with('"') {
+                        Quoting::Quoted
+                    } else if text.contains('\\') {
+                        Quoting::Escaped
+                    } else {
+                        Quoting::Bare
+                    };
+                    Kind::Label(tag.0.clone(), quoting)
+                }
+                Token::Open(open) => Kind::Open(open.clone()),
+                Token::Close(close) => Kind::Close(close.clone()),
+                Token::Sigspace => Kind::Space,
+            };
+
+            after_quote = lexeme.token == Open::Quote.into();
+            if after_quote {
+                self.opener = Some(indent);
+            }
+
+            self.push(kind, lexeme.span);
+        }
+    }
+
+    /// Add the text up to `offset` not covered by any lexeme: spacing and line endings
+    fn gap(&mut self, offset: usize) {
+        while self.cursor < offset {
+            let rest = self.source.get(self.cursor..offset).unwrap_or_default();
+            let (kind, len) = if rest.starts_with("\r\n") {
+                (Kind::Newline, 2)
+            } else if rest.starts_with(['\r', '\n']) {
+                (Kind::Newline, 1)
+            } else {
+                let len = rest.find(['\r', '\n']).unwrap_or(rest.len());
+                (Kind::Space, len)
+            };
+
+            let span = self.locator.span(self.cursor, self.cursor + len);
+            self.push(kind, span);
+        }
+    }
+
+    fn push(&mut self, kind: Kind<'a>, span: Span) {
+        if span.is_empty() {
+            return;
+        }
+
+        let newline = kind == Kind::Newline;
+        self.line.pieces.push(Piece {
+            kind,
+            text: self.text(&span),
+            span,
+        });
+        self.cursor = self.cursor.max(span.end.offset);
+
+        if newline {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+    }
+
+    fn text(&self, span: &Span) -> &'a str {
+        self.source.get(span.range()).unwrap_or_default()
+    }
+
+    fn finish(mut self) -> Cst<'a> {
+        self.gap(self.source.len());
+        if !self.line.pieces.is_empty() {
+            self.lines.push(self.line);
+        }
+
+        Cst {
+            source: self.source,
+            lines: self.lines,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::*;
+
+fn kinds(source: &str) -> Vec<Vec<(Kind<'_>, &str)>> {
+    Cst::parse(source)
+        .unwrap()
+        .lines
+        .into_iter()
+        .map(|line| {
+            line.pieces
+                .into_iter()
+                .map(|piece| (piece.kind, piece.text))
+                .collect()
+        })
+        .collect()
+}
+
+fn label(value: &str, quoting: Quoting) -> Kind<'_> {
+    Kind::Label(value.into(), quoting)
+}
+
+#[test]
+fn writes_back_byte_for_byte() {
+    let sources = [
+        "",
+        "\n\n",
+        "a",
+        "a\n",
+        "a  b\t c   \n",
+        "a\r\nb\r\n",
+        "a\rb\r",
+        "  \n\t\n",
+        "a( b  c )  d:e : f\n",
+        "\"quoted  label\" \\h ☃ es\\:caped \"\\t\"\n",
+        "mon\n   description \"  first\n      Plants healing bombs.\n\n      Standard attack.  \n   stride 2\n",
+        "a \"\n   trailing\n\n\n",
+        "a \"\n   no newline",
+    ];
+
+    for source in &sources {
+        let cst = Cst::parse(source).expect(source);
+        assert_eq!(cst.to_string(), *source);
+        assert_eq!(
+            cst.lines.len(),
+            source.split_inclusive(['\n', '\r']).count() - source.matches("\r\n").count(),
+            "{source:?}"
+        );
+    }
+}
+
+#[test]
+fn spacing_and_line_endings() {
+    assert_eq!(
+        kinds("  a  b(c )\r\n"),
+        vec![vec![
+            (Kind::Indent, "  "),
+            (label("a", Quoting::Bare), "a"),
+            (Kind::Space, "  "),
+            (label("b", Quoting::Bare), "b"),
+            (Kind::Open(Open::Paren), "("),
+            (label("c", Quoting::Bare), "c"),
+            (Kind::Space, " "),
+            (Kind::Close(Close::Paren), ")"),
+            (Kind::Newline, "\r\n"),
+        ]]
+    );
+}
+
+#[test]
+fn label_spellings() {
+    assert_eq!(
+        kinds("\\h ☃ \"☃\" \"a b\"\n"),
+        vec![vec![
+            (label("☃", Quoting::Escaped), "\\h"),
+            (Kind::Space, " "),
+            (label("☃", Quoting::Bare), "☃"),
+            (Kind::Space, " "),
+            (label("☃", Quoting::Quoted), "\"☃\""),
+            (Kind::Space, " "),
+            (label("a b", Quoting::Quoted), "\"a b\""),
+            (Kind::Newline, "\n"),
+        ]]
+    );
+}
+
+#[test]
+fn multiline_labels() {
+    assert_eq!(
+        kinds("a \"x\n  b (c\n\nd\n"),
+        vec![
+            vec![
+                (label("a", Quoting::Bare), "a"),
+                (Kind::Space, " "),
+                (Kind::Open(Open::Quote), "\""),
+                (label("x", Quoting::Multiline), "x"),
+                (Kind::Newline, "\n"),
+            ],
+            vec![
+                (Kind::Indent, "  "),
+                (label("b (c", Quoting::Multiline), "b (c"),
+                (Kind::Newline, "\n"),
+            ],
+            vec![(Kind::Newline, "\n")],
+            vec![(label("d", Quoting::Bare), "d"), (Kind::Newline, "\n")],
+        ]
+    );
+}
+
+#[test]
+fn multiline_ends_at_a_line_with_no_labels() {
+    let lines = kinds("a \"\n  b\n()\n  c\n");
+    assert_eq!(
+        lines[3],
+        vec![
+            (Kind::Indent, "  "),
+            (label("c", Quoting::Bare), "c"),
+            (Kind::Newline, "\n"),
+        ]
+    );
+}
+
+#[test]
+fn lines() {
+    let cst = Cst::parse("a\n  b\n   \n").unwrap();
+    let indents: Vec<&str> = cst.lines.iter().map(Line::indent).collect();
+    assert_eq!(indents, vec!["", "  ", "   "]);
+
+    let blank: Vec<bool> = cst.lines.iter().map(Line::is_blank).collect();
+    assert_eq!(blank, vec![false, false, true]);
+    assert_eq!(cst.lines[1].to_string(), "  b\n");
+}
+
+#[test]
+fn errors_like_the_lexer() {
+    assert_eq!(Cst::parse("a \\"), Err(::lexer::lex("a \\").unwrap_err()));
+}
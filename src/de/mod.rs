@@ -0,0 +1,756 @@
+//! Reading Rust values from termpose, with serde
+//!
+//! A value is read from the tail of the node it belongs to, so a struct field
+//! `health: u32` is read from `health 50`, and the fields of a struct from the
+//! nodes below it:
+//!
+//!  - structs and maps are nodes headed by their keys, each with the value as
+//!    its tail; the whole document is read this way too;
+//!  - numbers, bools, chars and strings are a single label, parsed as needed;
+//!  - sequences and tuples are a tail of items, such as the top-level nodes of a
+//!    document, or within an item, the head and tail of an s-list together:
+//!    `grid (1 2) (3 4)` is `[[1, 2], [3, 4]]`;
+//!  - an empty tail is `None` or `()`, and anything else `Some`;
+//!  - enum variants are a label, with any contents as its tail:
+//!    `shape circle:2` is `Shape::Circle(2.0)`.
+//!
+//! Where a struct or map is an item of a sequence, its head is ignored, so it
+//! can be named for readability.
+
+use error::Error as ParseError;
+use node::{Head, Node};
+use parse;
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use std::slice;
+
+#[cfg(test)]
+mod tests;
+
+/// What can go wrong reading a value from termpose
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The text isn't valid termpose
+    Parse(ParseError),
+
+    /// The tree doesn't fit the type being read
+    Data {
+        message: String,
+
+        /// The line of the node the problem was found at
+        line: Option<usize>,
+    },
+}
+
+impl Error {
+    /// The line the problem was found on, if it was found at a node
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Error::Parse(err) => Some(err.position().line),
+            Error::Data { line, .. } => *line,
+        }
+    }
+
+    /// Point an error at `line`, unless it already points somewhere
+    fn at(self, at: Option<usize>) -> Self {
+        match self {
+            Error::Data {
+                message,
+                line: None,
+            } => Error::Data { message, line: at },
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(err) => err.fmt(f),
+            Error::Data {
+                message,
+                line: Some(line),
+            } => write!(f, "{message} at line {line}"),
+            Error::Data {
+                message,
+                line: None,
+            } => f.write_str(message),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Parse(err) => Some(err),
+            Error::Data { .. } => None,
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Data {
+            message: msg.to_string(),
+            line: None,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+/// Parse a termpose document and read a value from it
+///
+/// # Errors
+///
+/// If the input isn't valid termpose, or doesn't fit `T`.
+pub fn from_str<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, Error> {
+    from_nodes(&parse(input)?)
+}
+
+/// Read a value from the top-level nodes of a document, or the tail of a node
+///
+/// # Errors
+///
+/// If the nodes don't fit `T`.
+pub fn from_nodes<'de, T: Deserialize<'de>>(nodes: &[Node<'de>]) -> Result<T, Error> {
+    T::deserialize(Tail { nodes, line: None })
+}
+
+fn error(message: &str, line: usize) -> Error {
+    Error::Data {
+        message: message.into(),
+        line: Some(line),
+    }
+}
+
+/// Deserializes from the tail of a node, or the top level of a document
+#[derive(Clone, Copy)]
+struct Tail<'a, 'de> {
+    nodes: &'a [Node<'de>],
+
+    /// The line of the node the tail belongs to
+    line: Option<usize>,
+}
+
+impl<'a, 'de> Tail<'a, 'de> {
+    /// The single item in the tail
+    fn single(self) -> Result<Item<'a, 'de>, Error> {
+        match self.nodes {
+            [node] => Ok(Item(node)),
+            [] => Err(de::Error::custom("expected a value, found nothing")),
+            _ => Err(de::Error::custom(format!(
+                "expected a single value, found {}",
+                self.nodes.len()
+            ))),
+        }
+    }
+
+    fn located<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|err| err.at(self.line))
+    }
+}
+
+/// Whether nodes all have a label head and a tail, with no label twice
+fn keyed(nodes: &[Node]) -> bool {
+    nodes.iter().enumerate().all(|(i, node)| match node.name() {
+        Some(name) => {
+            !node.children.is_empty()
+                && !nodes.iter().take(i).any(|other| other.name() == Some(name))
+        }
+        None => false,
+    })
+}
+
+macro_rules! single {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let result = self.single().and_then(|item| item.$method(visitor));
+                self.located(result)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tail<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.nodes {
+            [] => visitor.visit_unit(),
+            [node] if node.is_label() => Item(node).deserialize_any(visitor),
+            nodes if keyed(nodes) => visitor.visit_map(Entries::new(nodes)),
+            nodes => visitor.visit_seq(Parts::new(None, nodes)),
+        };
+        self.located(result)
+    }
+
+    single! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = if self.nodes.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        };
+        self.located(result)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.nodes.first() {
+            None => visitor.visit_unit(),
+            Some(node) => Err(error("expected nothing", node.line)),
+        };
+        self.located(result)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.located(visitor.visit_newtype_struct(self))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_seq(Parts::new(None, self.nodes)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_map(Entries::new(self.nodes)))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let result = self
+            .single()
+            .and_then(|item| item.deserialize_enum(name, variants, visitor));
+        self.located(result)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializes from a single node, as an item of a tail
+#[derive(Clone, Copy)]
+struct Item<'a, 'de>(&'a Node<'de>);
+
+impl<'a, 'de> Item<'a, 'de> {
+    /// The node as a lone label
+    fn label(self) -> Result<Label<'a, 'de>, Error> {
+        match &self.0.head {
+            Some(Head::Label(name)) if self.0.children.is_empty() => Ok(Label {
+                name,
+                line: self.0.line,
+            }),
+            _ => Err(error("expected a single label", self.0.line)),
+        }
+    }
+
+    fn located<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|err| err.at(Some(self.0.line)))
+    }
+}
+
+macro_rules! label {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.label()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Item<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let node = self.0;
+        let result = match &node.head {
+            Some(Head::Label(_)) if node.children.is_empty() => {
+                return self.label()?.deserialize_any(visitor)
+            }
+            Some(Head::Label(_)) => visitor.visit_map(Entries::new(slice::from_ref(node))),
+            None if node.children.is_empty() => visitor.visit_unit(),
+            _ => visitor.visit_seq(Parts::of(node)),
+        };
+        self.located(result)
+    }
+
+    label! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = if self.0.is_nil() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        };
+        self.located(result)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_nil() {
+            self.located(visitor.visit_unit())
+        } else {
+            Err(error("expected ()", self.0.line))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.located(visitor.visit_newtype_struct(self))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_seq(Parts::of(self.0)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_map(Entries::new(&self.0.children)))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let node = self.0;
+        let Some(Head::Label(name)) = &node.head else {
+            return Err(error("expected a variant name", node.line));
+        };
+
+        let variant = Variant {
+            name: Label {
+                name,
+                line: node.line,
+            },
+            content: Tail {
+                nodes: &node.children,
+                line: Some(node.line),
+            },
+        };
+        self.located(visitor.visit_enum(variant))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializes from a label: a lone label, a key, or the head of an s-list
+#[derive(Clone, Copy)]
+struct Label<'a, 'de> {
+    name: &'a Cow<'de, str>,
+    line: usize,
+}
+
+impl Label<'_, '_> {
+    fn invalid<V: de::Expected>(self, expected: &V) -> Error {
+        de::Error::invalid_value(Unexpected::Str(self.name), expected)
+    }
+
+    fn located<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|err| err.at(Some(self.line)))
+    }
+}
+
+macro_rules! parsed {
+    ($($method:ident $visit:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let result = match self.name.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(self.invalid(&visitor)),
+                };
+                self.located(result)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Label<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    parsed! {
+        deserialize_bool visit_bool
+        deserialize_i8 visit_i8
+        deserialize_i16 visit_i16
+        deserialize_i32 visit_i32
+        deserialize_i64 visit_i64
+        deserialize_i128 visit_i128
+        deserialize_u8 visit_u8
+        deserialize_u16 visit_u16
+        deserialize_u32 visit_u32
+        deserialize_u64 visit_u64
+        deserialize_u128 visit_u128
+        deserialize_f32 visit_f32
+        deserialize_f64 visit_f64
+        deserialize_char visit_char
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.name {
+            Cow::Borrowed(name) => visitor.visit_borrowed_str(name),
+            Cow::Owned(name) => visitor.visit_str(name),
+        };
+        self.located(result)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.name {
+            Cow::Borrowed(name) => visitor.visit_borrowed_bytes(name.as_bytes()),
+            Cow::Owned(name) => visitor.visit_bytes(name.as_bytes()),
+        };
+        self.located(result)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_some(self))
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        Err(self.invalid(&visitor).at(Some(self.line)))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.located(visitor.visit_newtype_struct(self))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.located(visitor.visit_seq(Parts::new(Some(Part::Label(self)), &[])))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        Err(self.invalid(&visitor).at(Some(self.line)))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let variant = Variant {
+            name: self,
+            content: Tail {
+                nodes: &[],
+                line: Some(self.line),
+            },
+        };
+        self.located(visitor.visit_enum(variant))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// The items of a sequence: maybe a head, then nodes
+struct Parts<'a, 'de> {
+    head: Option<Part<'a, 'de>>,
+    nodes: slice::Iter<'a, Node<'de>>,
+}
+
+/// The head of an s-list, as an item of a sequence
+enum Part<'a, 'de> {
+    Label(Label<'a, 'de>),
+    List(Item<'a, 'de>),
+}
+
+impl<'a, 'de> Parts<'a, 'de> {
+    fn new(head: Option<Part<'a, 'de>>, nodes: &'a [Node<'de>]) -> Self {
+        Self {
+            head,
+            nodes: nodes.iter(),
+        }
+    }
+
+    /// The head and tail of an s-list together
+    fn of(node: &'a Node<'de>) -> Self {
+        let head = match &node.head {
+            Some(Head::Label(name)) => Some(Part::Label(Label {
+                name,
+                line: node.line,
+            })),
+            Some(Head::List(list)) => Some(Part::List(Item(list))),
+            None => None,
+        };
+        Self::new(head, &node.children)
+    }
+}
+
+impl<'de> SeqAccess<'de> for Parts<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        let item = match self.head.take() {
+            Some(Part::Label(label)) => return seed.deserialize(label).map(Some),
+            Some(Part::List(item)) => item,
+            None => match self.nodes.next() {
+                Some(node) => Item(node),
+                None => return Ok(None),
+            },
+        };
+        seed.deserialize(item).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(usize::from(self.head.is_some()) + self.nodes.len())
+    }
+}
+
+/// The entries of a map or struct: nodes headed by their keys
+struct Entries<'a, 'de> {
+    nodes: slice::Iter<'a, Node<'de>>,
+    value: Option<Tail<'a, 'de>>,
+}
+
+impl<'a, 'de> Entries<'a, 'de> {
+    fn new(nodes: &'a [Node<'de>]) -> Self {
+        Self {
+            nodes: nodes.iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some(node) = self.nodes.next() else {
+            return Ok(None);
+        };
+
+        let Some(Head::Label(name)) = &node.head else {
+            return Err(error("expected a label for a key", node.line));
+        };
+
+        self.value = Some(Tail {
+            nodes: &node.children,
+            line: Some(node.line),
+        });
+        let key = Label {
+            name,
+            line: node.line,
+        };
+        key.located(seed.deserialize(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let Some(value) = self.value.take() else {
+            return Err(de::Error::custom("value asked for before its key"));
+        };
+        value.located(seed.deserialize(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.nodes.len())
+    }
+}
+
+/// An enum variant: its name, and any contents
+struct Variant<'a, 'de> {
+    name: Label<'a, 'de>,
+    content: Tail<'a, 'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Variant<'a, 'de> {
+    type Error = Error;
+    type Variant = Tail<'a, 'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = self.name.located(seed.deserialize(self.name))?;
+        Ok((variant, self.content))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Tail<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserializer::deserialize_unit(self, de::IgnoredAny).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        self.located(seed.deserialize(self))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
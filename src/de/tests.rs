@@ -0,0 +1,165 @@
+use super::*;
+use std::collections::BTreeMap;
+
+const MON: &str = "mon
+    name \"courageous leafward\"
+    stride 2
+    health 50
+    speed 1.5
+    tame true
+    tags plant healer
+    abilities
+        ability
+            name strike
+            power 3
+        ability
+            name move
+    resist fire:0.5 water:2
+    shape circle:4
+    mood calm
+";
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Doc {
+    mon: Mon,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Mon {
+    name: String,
+    stride: u8,
+    health: i64,
+    speed: f64,
+    tame: bool,
+    tags: Vec<String>,
+    abilities: Vec<Ability>,
+    resist: BTreeMap<String, f32>,
+    shape: Shape,
+    mood: Mood,
+    owner: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Ability {
+    name: String,
+    power: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Shape {
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+    Line(u32, u32),
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Mood {
+    Calm,
+    Angry,
+}
+
+#[test]
+fn whole_document() {
+    let doc: Doc = from_str(MON).unwrap();
+    assert_eq!(
+        doc.mon,
+        Mon {
+            name: "courageous leafward".into(),
+            stride: 2,
+            health: 50,
+            speed: 1.5,
+            tame: true,
+            tags: vec!["plant".into(), "healer".into()],
+            abilities: vec![
+                Ability {
+                    name: "strike".into(),
+                    power: Some(3),
+                },
+                Ability {
+                    name: "move".into(),
+                    power: None,
+                },
+            ],
+            resist: vec![("fire".into(), 0.5), ("water".into(), 2.0)]
+                .into_iter()
+                .collect(),
+            shape: Shape::Circle(4),
+            mood: Mood::Calm,
+            owner: None,
+        }
+    );
+}
+
+#[test]
+fn enums() {
+    assert_eq!(
+        from_str::<Vec<Mood>>("calm\nangry\n"),
+        Ok(vec![Mood::Calm, Mood::Angry])
+    );
+    assert_eq!(
+        from_str::<Shape>("rect\n    w 2\n    h 3\n"),
+        Ok(Shape::Rect { w: 2, h: 3 })
+    );
+    assert_eq!(from_str::<Shape>("line 2 3\n"), Ok(Shape::Line(2, 3)));
+    assert_eq!(
+        from_str::<Vec<Shape>>("circle:1\nline(2 3)\n").map(|s| s.len()),
+        Ok(2)
+    );
+}
+
+#[test]
+fn sequences() {
+    assert_eq!(from_str::<Vec<u8>>("1\n2\n3\n"), Ok(vec![1, 2, 3]));
+    assert_eq!(from_str::<Vec<u8>>(""), Ok(vec![]));
+    assert_eq!(
+        from_str::<Vec<Vec<u8>>>("1 2\n3 4 5\n6\n"),
+        Ok(vec![vec![1, 2], vec![3, 4, 5], vec![6]])
+    );
+    assert_eq!(
+        from_str::<(String, i32, char)>("a\n-1\nc\n"),
+        Ok(("a".into(), -1, 'c'))
+    );
+}
+
+#[test]
+fn maps() {
+    let map: BTreeMap<u32, Vec<String>> = from_str("1 a b\n2 c\n").unwrap();
+    assert_eq!(map[&1], ["a", "b"]);
+    assert_eq!(map[&2], ["c"]);
+}
+
+#[test]
+fn borrows_from_the_source() {
+    #[derive(Deserialize)]
+    struct Named<'a> {
+        name: &'a str,
+    }
+
+    let source = String::from("name leafward\n");
+    let named: Named = from_str(&source).unwrap();
+    assert_eq!(named.name, "leafward");
+}
+
+#[test]
+fn errors_point_at_lines() {
+    let line = |input: &str| from_str::<Doc>(input).unwrap_err().line();
+
+    assert_eq!(line(&MON.replace("health 50", "health fifty")), Some(4));
+    assert_eq!(line(&MON.replace("    health 50\n", "")), Some(1));
+    assert_eq!(line(&MON.replace("power 3", "power 3 4")), Some(11));
+    assert_eq!(line(&MON.replace("mood calm", "mood sad")), Some(16));
+    assert_eq!(line(&MON.replace("tame true", "tame(true false)")), Some(6));
+    assert_eq!(line("mon\n  a)\n"), Some(2));
+
+    let err = from_str::<Doc>(&MON.replace("health 50", "health fifty")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value: string \"fifty\", expected i64 at line 4"
+    );
+    assert_eq!(
+        from_str::<Doc>("").unwrap_err().to_string(),
+        "missing field `mon`"
+    );
+}
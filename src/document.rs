@@ -0,0 +1,32 @@
+use node::Node;
+use position::Span;
+
+/// A whole termpose document
+///
+/// The document itself isn't an s-list, just the s-lists at its top level.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document<'a> {
+    pub children: Vec<Node<'a>>,
+
+    /// From the start of the first node to the end of the last
+    pub span: Span,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(children: Vec<Node<'a>>) -> Self {
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => Span::new(first.span.start, last.span.end),
+            _ => Span::default(),
+        };
+
+        Self { children, span }
+    }
+
+    /// Copy anything borrowed, so the document no longer depends on the source
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+            span: self.span,
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use cst::{Cst, Kind};
+use document::Document;
+use error::Error;
+use lexer::Open;
+use node::{Head, Node};
+use parse_document;
+use position::Position;
+use printer::{label, Style};
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::Range;
+use std::{ptr, slice};
+
+#[cfg(test)]
+mod tests;
+
+/// Everything that can go wrong while editing a document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// There's no node at the path
+    NoSuchNode(Vec<usize>),
+
+    /// The node is headed by an s-list or nothing, not a label
+    NotALabel(Position),
+
+    /// The node is within a line its parent doesn't start, so there's no text
+    /// of its own to edit
+    Stranded(Position),
+
+    /// The edit would have changed how the nodes around the one at this
+    /// position read, so it wasn't made
+    Reshaped(Position),
+
+    /// The edit would have left the document invalid, so it wasn't made
+    Invalid(Error),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::NoSuchNode(path) => write!(f, "no node at path {path:?}"),
+            EditError::NotALabel(pos) => write!(f, "node at {pos} isn't headed by a label"),
+            EditError::Stranded(pos) => {
+                write!(f, "node at {pos} is within a line its parent doesn't start")
+            }
+            EditError::Reshaped(pos) => {
+                write!(f, "edit at {pos} would change the nodes around it")
+            }
+            EditError::Invalid(err) => write!(f, "edit would leave the document invalid: {err}"),
+        }
+    }
+}
+
+impl StdError for EditError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            EditError::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Some text to put in place of a range of the source
+type Splice = (Range<usize>, String);
+
+/// Edits a termpose document as text, leaving what it doesn't touch as it was
+///
+/// Nodes are found by path: the index of a top-level node, then of a child
+/// within that, and so on. Children on lines of their own are inserted and
+/// removed a line at a time; those within a line are edited in place, and the
+/// rest of that line is kept as written, but for the spacing between items.
+///
+/// Every edit is checked by parsing its result, and is only made if that works.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Editor {
+    source: String,
+}
+
+impl Editor {
+    /// Start editing a document
+    ///
+    /// # Errors
+    ///
+    /// If the source isn't a valid termpose document.
+    pub fn new<S: Into<String>>(source: S) -> Result<Self, Error> {
+        let source = source.into();
+        parse_document(&source)?;
+        Ok(Self { source })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn into_string(self) -> String {
+        self.source
+    }
+
+    /// The document as it stands
+    pub fn document(&self) -> Document<'_> {
+        // Every edit is checked, so the source always parses
+        parse_document(&self.source).unwrap_or_default()
+    }
+
+    /// Change the label at the head of a node, keeping its tail
+    ///
+    /// # Errors
+    ///
+    /// If there's no node at `path`, or it isn't headed by a label.
+    pub fn rename(&mut self, path: &[usize], name: &str) -> Result<(), EditError> {
+        let splice = {
+            let view = View::new(&self.source);
+            let node = view.find(path)?;
+            if node.name().is_none() {
+                return Err(EditError::NotALabel(node.head_span.start));
+            }
+
+            (node.head_span.range(), label(name).into_owned())
+        };
+
+        self.splice(splice)
+    }
+
+    /// Make a node's tail the single label `value`, as in `health 50`
+    ///
+    /// A tail which is already a single label is renamed in place; any other is
+    /// removed, and the label put in its place.
+    ///
+    /// # Errors
+    ///
+    /// If there's no node at `path`. The document is left as it was.
+    pub fn set_label(&mut self, path: &[usize], value: &str) -> Result<(), EditError> {
+        let labels: Vec<bool> = {
+            let view = View::new(&self.source);
+            view.find(path)?
+                .children
+                .iter()
+                .map(Node::is_label)
+                .collect()
+        };
+
+        if labels == [true] {
+            return self.rename(&child(path, 0), value);
+        }
+
+        self.atomically(|editor| {
+            for i in (0..labels.len()).rev() {
+                editor.remove(&child(path, i))?;
+            }
+
+            editor.insert(path, 0, &Node::new(value, "", 0))
+        })
+    }
+
+    /// Insert a node as the `index`th child of the node at `path`
+    ///
+    /// An empty path inserts a top-level node. Where the children at that point
+    /// are on lines of their own, the node goes on new lines, indented to match;
+    /// otherwise it goes within the line.
+    ///
+    /// # Errors
+    ///
+    /// If there's no node at `path`, or `index` is past the end of its tail.
+    pub fn insert(&mut self, path: &[usize], index: usize, node: &Node) -> Result<(), EditError> {
+        let splice = {
+            let view = View::new(&self.source);
+            let siblings = view.children(path)?;
+            if index > siblings.len() {
+                return Err(EditError::NoSuchNode(child(path, index)));
+            }
+
+            let inline = siblings
+                .iter()
+                .take_while(|sibling| !view.is_line_node(sibling))
+                .count();
+            let parent = match path {
+                [] => None,
+                _ => Some(view.find(path)?),
+            };
+
+            match parent {
+                Some(parent)
+                    if index < inline || inline == siblings.len() || !view.is_line_node(parent) =>
+                {
+                    let text = render(node);
+                    view.rewrite(parent, |children| children.insert(index, text))
+                }
+                _ => view.insert_lines(parent, siblings, index, node),
+            }
+        };
+
+        self.splice(splice)
+    }
+
+    /// Remove a node and everything in it
+    ///
+    /// # Errors
+    ///
+    /// If there's no node at `path`, it's within a line its parent doesn't
+    /// start, or its siblings would read differently without it.
+    pub fn remove(&mut self, path: &[usize]) -> Result<(), EditError> {
+        let (splice, expected, at) = {
+            let view = View::new(&self.source);
+            let node = view.find(path)?;
+            let mut expected = view.doc.clone();
+            if let Some((&index, parent)) = path.split_last() {
+                let tail = parent
+                    .iter()
+                    .fold(&mut expected.children, |tail, &i| &mut tail[i].children);
+                tail.remove(index);
+            }
+
+            let splice = match path.split_last() {
+                Some((_, parent)) if !view.is_line_node(node) => {
+                    let parent = view.find(parent)?;
+                    let index = view
+                        .within(parent)
+                        .iter()
+                        .position(|child| ptr::eq(*child, node))
+                        .ok_or(EditError::Stranded(node.span.start))?;
+                    view.rewrite(parent, |children| {
+                        children.remove(index);
+                    })
+                }
+                _ => {
+                    let start = view.line_range(node.span.start.line).start;
+                    let end = view.line_range(node.span.end.line).end;
+                    (start..end, String::new())
+                }
+            };
+            (splice, expected.to_string(), node.span.start)
+        };
+
+        let before = self.source.clone();
+        self.splice(splice)?;
+        if parse_document(&self.source).map(|doc| doc.to_string()) != Ok(expected) {
+            self.source = before;
+            return Err(EditError::Reshaped(at));
+        }
+        Ok(())
+    }
+
+    /// Make an edit, if the result parses
+    fn splice(&mut self, (range, text): Splice) -> Result<(), EditError> {
+        let mut source = self.source.clone();
+        source.replace_range(range, &text);
+        parse_document(&source).map_err(EditError::Invalid)?;
+        self.source = source;
+        Ok(())
+    }
+
+    /// Make several edits, or none of them if any fails
+    fn atomically<F>(&mut self, edits: F) -> Result<(), EditError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), EditError>,
+    {
+        let before = self.source.clone();
+        edits(self).inspect_err(|_| self.source = before)
+    }
+}
+
+impl fmt::Display for Editor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Write a new node within a line
+fn render(node: &Node) -> String {
+    let mut out = String::new();
+    Style::default().item(&mut out, node);
+    out
+}
+
+fn child(path: &[usize], index: usize) -> Vec<usize> {
+    path.iter().copied().chain(Some(index)).collect()
+}
+
+/// The source as both a tree and lines, for working out edits
+struct View<'s> {
+    source: &'s str,
+    doc: Document<'s>,
+    cst: Cst<'s>,
+}
+
+impl<'s> View<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            doc: parse_document(source).unwrap_or_default(),
+            cst: Cst::parse(source).unwrap_or_default(),
+        }
+    }
+
+    fn find(&self, path: &[usize]) -> Result<&Node<'s>, EditError> {
+        let missing = || EditError::NoSuchNode(path.to_vec());
+        let (first, rest) = path.split_first().ok_or_else(missing)?;
+        rest.iter()
+            .try_fold(self.doc.children.get(*first), |node, &i| {
+                node.map(|node| node.children.get(i))
+            })
+            .flatten()
+            .ok_or_else(missing)
+    }
+
+    /// The tail of the node at `path`, or the top-level nodes for an empty path
+    fn children(&self, path: &[usize]) -> Result<&[Node<'s>], EditError> {
+        match path {
+            [] => Ok(&self.doc.children),
+            _ => self.find(path).map(|node| &node.children[..]),
+        }
+    }
+
+    /// Whether a node starts its line, rather than being within one
+    ///
+    /// Colons opening the line come before the node they're applied to, so
+    /// they don't stop it from starting the line.
+    fn is_line_node(&self, node: &Node) -> bool {
+        self.line(node.span.start.line)
+            .and_then(|line| {
+                line.pieces.iter().find(|p| {
+                    !matches!(p.kind, Kind::Indent | Kind::Space | Kind::Open(Open::Colon))
+                })
+            })
+            .is_some_and(|first| first.span.start == node.span.start)
+    }
+
+    /// The part of `parent`'s tail within a line: for a node starting a line,
+    /// the children up to the first on a line of its own; for any other node,
+    /// all of them
+    fn within<'n>(&self, parent: &'n Node<'s>) -> Vec<&'n Node<'s>> {
+        let starts_line = self.is_line_node(parent);
+        parent
+            .children
+            .iter()
+            .take_while(|child| !starts_line || !self.is_line_node(child))
+            .collect()
+    }
+
+    fn line(&self, line: usize) -> Option<&::cst::Line<'s>> {
+        self.cst.lines.get(line.checked_sub(1)?)
+    }
+
+    /// Where a line is in the source, including its line ending
+    fn line_range(&self, line: usize) -> Range<usize> {
+        let pieces = self.line(line).map(|line| &line.pieces[..]);
+        match pieces.map(|pieces| (pieces.first(), pieces.last())) {
+            Some((Some(first), Some(last))) => first.span.start.offset..last.span.end.offset,
+            _ => self.source.len()..self.source.len(),
+        }
+    }
+
+    fn indent(&self, line: usize) -> &'s str {
+        self.line(line).map_or("", ::cst::Line::indent)
+    }
+
+    /// The first line ending in the source, to write new ones like it
+    fn newline(&self) -> &'s str {
+        self.cst
+            .pieces()
+            .find(|piece| piece.kind == Kind::Newline)
+            .map_or("\n", |piece| piece.text)
+    }
+
+    /// Write a node within a line, as it was written if it can stay that way
+    fn text(&self, node: &Node) -> String {
+        match self.source.get(node.span.range()) {
+            Some(text) if !text.contains(['\r', '\n']) => text.into(),
+            _ => render(node),
+        }
+    }
+
+    /// Write a node's head within a line, as it was written if it can stay that way
+    fn head_text(&self, node: &Node) -> String {
+        match (&node.head, self.source.get(node.head_span.range())) {
+            (Some(_), Some(text)) if !text.contains(['\r', '\n']) => text.into(),
+            (Some(Head::Label(name)), _) => label(name).into_owned(),
+            (Some(Head::List(list)), _) => self.text(list),
+            (None, _) => "()".into(),
+        }
+    }
+
+    /// Rewrite the part of `parent` within a line, after changing the text of
+    /// its tail there
+    ///
+    /// For a node starting a line, that's the line up to the end of the last
+    /// child on it; for any other node, it's the whole node.
+    fn rewrite<F>(&self, parent: &Node<'s>, change: F) -> Splice
+    where
+        F: FnOnce(&mut Vec<String>),
+    {
+        let starts_line = self.is_line_node(parent);
+        let within = self.within(parent);
+        let end = within
+            .last()
+            .map_or(parent.head_span.end, |child| child.span.end);
+
+        let mut children: Vec<String> = within.iter().map(|child| self.text(child)).collect();
+        change(&mut children);
+        let head = self.head_text(parent);
+
+        let text = match (&parent.head, &children[..]) {
+            (_, []) => head,
+            _ if starts_line => format!("{head} {}", children.join(" ")),
+            (Some(Head::Label(_)), _) => format!("{head}({})", children.join(" ")),
+            _ => format!("({head} {})", children.join(" ")),
+        };
+
+        let range = if starts_line {
+            parent.span.start.offset..end.offset
+        } else {
+            parent.span.range()
+        };
+        (range, text)
+    }
+
+    /// Insert a node on lines of its own, before the `index`th of `siblings`
+    fn insert_lines(
+        &self,
+        parent: Option<&Node>,
+        siblings: &[Node],
+        index: usize,
+        node: &Node,
+    ) -> Splice {
+        let (at, indent) = match (siblings.get(index), siblings.last()) {
+            (Some(next), _) => (
+                self.line_range(next.span.start.line).start,
+                self.indent(next.span.start.line),
+            ),
+            (None, Some(last)) => (
+                self.line_range(last.span.end.line).end,
+                self.indent(last.span.start.line),
+            ),
+            (None, None) => (self.source.len(), ""),
+        };
+
+        let outer = parent.map_or("", |parent| self.indent(parent.span.start.line));
+        let step = match indent.strip_prefix(outer) {
+            Some(step) if !step.is_empty() => step,
+            _ => self.step(),
+        };
+
+        let newline = self.newline();
+        let before = self.source.get(..at).unwrap_or_default();
+        let mut text = String::new();
+        if !before.is_empty() && !before.ends_with(['\r', '\n']) {
+            text.push_str(newline);
+        }
+
+        let printed = Style::default()
+            .with_indent(step)
+            .print(slice::from_ref(node));
+        for line in printed.lines() {
+            if !line.is_empty() {
+                text.push_str(indent);
+                text.push_str(line);
+            }
+            text.push_str(newline);
+        }
+
+        (at..at, text)
+    }
+
+    /// The first indent in the source, taken to be how deep each level goes
+    fn step(&self) -> &'s str {
+        self.cst
+            .lines
+            .iter()
+            .filter(|line| !line.is_blank())
+            .map(::cst::Line::indent)
+            .find(|indent| !indent.is_empty())
+            .unwrap_or("    ")
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+
+const MON: &str = "mon
+   name  \"courageous leafward\"
+   description \"
+      Plants healing bombs.
+      Standard attack.
+
+   stride 2
+   health 50
+   abilities
+      move
+      strike drain:2 effect( damage:2 )
+";
+
+fn edit<F>(source: &str, change: F) -> String
+where
+    F: FnOnce(&mut Editor) -> Result<(), EditError>,
+{
+    let mut editor = Editor::new(source).unwrap();
+    change(&mut editor).unwrap();
+    editor.into_string()
+}
+
+#[test]
+fn bump_a_value() {
+    assert_eq!(
+        edit(MON, |e| e.set_label(&[0, 3], "60")),
+        MON.replace("health 50", "health 60")
+    );
+}
+
+#[test]
+fn rename_keeps_the_rest() {
+    assert_eq!(
+        edit(MON, |e| e.rename(&[0], "monster")),
+        MON.replacen("mon", "monster", 1)
+    );
+    assert_eq!(
+        edit(MON, |e| e.rename(&[0, 0], "full name")),
+        MON.replace("   name ", "   \"full name\" ")
+    );
+    assert_eq!(
+        edit(MON, |e| e.rename(&[0, 4, 1, 0], "cost")),
+        MON.replace("drain:2", "cost:2")
+    );
+}
+
+#[test]
+fn rename_needs_a_label_head() {
+    let mut editor = Editor::new("(a b) c\n").unwrap();
+    assert_eq!(
+        editor.rename(&[0], "x"),
+        Err(EditError::NotALabel(Position::new(1, 1, 0)))
+    );
+    assert_eq!(editor.source(), "(a b) c\n");
+}
+
+#[test]
+fn missing_nodes() {
+    let mut editor = Editor::new("a b\n").unwrap();
+    assert_eq!(editor.rename(&[], "x"), Err(EditError::NoSuchNode(vec![])));
+    assert_eq!(
+        editor.remove(&[0, 1]),
+        Err(EditError::NoSuchNode(vec![0, 1]))
+    );
+    assert_eq!(
+        editor.insert(&[0], 2, &Node::new("c", "", 0)),
+        Err(EditError::NoSuchNode(vec![0, 2]))
+    );
+}
+
+#[test]
+fn remove_lines() {
+    assert_eq!(
+        edit(MON, |e| e.remove(&[0, 4])),
+        MON.replace(
+            "   abilities\n      move\n      strike drain:2 effect( damage:2 )\n",
+            ""
+        )
+    );
+    assert_eq!(
+        edit(MON, |e| e.remove(&[0, 1])),
+        MON.replace(
+            "   description \"\n      Plants healing bombs.\n      Standard attack.\n",
+            ""
+        )
+    );
+    assert_eq!(edit("a\nb\nc", |e| e.remove(&[2])), "a\nb\n");
+}
+
+#[test]
+fn remove_within_a_line() {
+    assert_eq!(
+        edit(MON, |e| e.remove(&[0, 4, 1, 1])),
+        MON.replace("strike drain:2 effect( damage:2 )", "strike drain:2")
+    );
+    assert_eq!(
+        edit(MON, |e| e.remove(&[0, 4, 1, 1, 0])),
+        MON.replace("effect( damage:2 )", "effect")
+    );
+    assert_eq!(edit("a b c\n", |e| e.remove(&[0, 0])), "a c\n");
+
+    let mut editor = Editor::new("(h(x)) y\n    z\n").unwrap();
+    assert_eq!(
+        editor.remove(&[0, 0]),
+        Err(EditError::Reshaped(Position::new(1, 8, 7)))
+    );
+    assert_eq!(editor.source(), "(h(x)) y\n    z\n");
+}
+
+#[test]
+fn insert_lines() {
+    let wand = Node::new("wand", "", 0);
+    assert_eq!(
+        edit(MON, |e| e.insert(&[0, 4], 1, &wand)),
+        MON.replace("      move\n", "      move\n      wand\n")
+    );
+    assert_eq!(
+        edit(MON, |e| e.insert(&[0, 4], 2, &wand)),
+        MON.replace("damage:2 )\n", "damage:2 )\n      wand\n")
+    );
+    assert_eq!(
+        edit(MON, |e| e.insert(&[], 1, &wand)),
+        format!("{MON}wand\n")
+    );
+    assert_eq!(edit("a\nc\n", |e| e.insert(&[], 1, &wand)), "a\nwand\nc\n");
+    assert_eq!(
+        edit("a\r\n  b", |e| e.insert(&[0], 1, &wand)),
+        "a\r\n  b\r\n  wand\r\n"
+    );
+}
+
+#[test]
+fn insert_lines_with_children() {
+    let mut wand = Node::new("wand", "", 0);
+    wand.children.push(Node::new("oak", "", 0));
+    wand.children.push(Node::new("a\nlong\n\nstory", "", 0));
+
+    assert_eq!(
+        edit("a\n\tb\n", |e| e.insert(&[0], 1, &wand)),
+        "a\n\tb\n\twand\n\t\toak\n\t\t\"\n\t\t\ta\n\t\t\tlong\n\n\t\t\tstory\n"
+    );
+    assert_eq!(
+        edit("x\n  y\n", |e| e.insert(&[], 0, &wand)),
+        "wand\n  oak\n  \"\n    a\n    long\n\n    story\nx\n  y\n"
+    );
+}
+
+#[test]
+fn insert_within_a_line() {
+    let wand = Node::new("wand", "", 0);
+    assert_eq!(edit("a  b\n", |e| e.insert(&[0], 0, &wand)), "a wand b\n");
+    assert_eq!(edit("a  b\n", |e| e.insert(&[0], 1, &wand)), "a b wand\n");
+    assert_eq!(edit("a\n", |e| e.insert(&[0], 0, &wand)), "a wand\n");
+    assert_eq!(
+        edit("a b:c\n", |e| e.insert(&[0, 0], 1, &wand)),
+        "a b(c wand)\n"
+    );
+    assert_eq!(
+        edit("a b\n", |e| e.insert(&[0, 0], 0, &wand)),
+        "a b(wand)\n"
+    );
+    assert_eq!(
+        edit("a b\n    c\n", |e| e.insert(&[0], 1, &wand)),
+        "a b\n    wand\n    c\n"
+    );
+    assert_eq!(
+        edit("(x y) z\n", |e| e.insert(&[0], 1, &wand)),
+        "(x y) z wand\n"
+    );
+}
+
+#[test]
+fn set_label_replaces_the_tail() {
+    assert_eq!(edit("a b c\n", |e| e.set_label(&[0], "d")), "a d\n");
+    assert_eq!(
+        edit("a\n    b\n    c\nz\n", |e| e.set_label(&[0], "d")),
+        "a d\nz\n"
+    );
+    assert_eq!(edit("a\n", |e| e.set_label(&[0], "d e")), "a \"d e\"\n");
+}
+
+#[test]
+fn insert_nil() {
+    let mut editor = Editor::new("a\n    b\n").unwrap();
+    assert_eq!(editor.insert(&[0, 0], 0, &Node::nil("", 0)), Ok(()));
+    assert_eq!(editor.source(), "a\n    b ()\n");
+    assert!(editor.document().children[0].children[0].children[0].is_nil());
+}
+
+#[test]
+fn lines_opened_by_colons() {
+    assert_eq!(
+        edit("a\n    b\n    :c\n", |e| e.remove(&[0, 1])),
+        "a\n    b\n"
+    );
+    assert_eq!(
+        edit("a\n    b\n    :c\n", |e| e.set_label(&[0], "7")),
+        "a 7\n"
+    );
+    assert_eq!(
+        edit("a\n    b\n    : c\n", |e| e.insert(
+            &[0],
+            1,
+            &Node::new("d", "", 0)
+        )),
+        "a\n    b\n    d\n    : c\n"
+    );
+    assert_eq!(
+        edit("bab\\(\n \r x\n\r ::a(", |e| e.remove(&[0, 1])),
+        "bab\\(\n \r x\n\r"
+    );
+}
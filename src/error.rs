@@ -0,0 +1,66 @@
+use position::Position;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Everything that can go wrong while parsing a termpose document
+///
+/// Each variant carries the position in the source where the problem was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A quoted label was opened but never closed
+    UnterminatedQuote(Position),
+
+    /// An escape sequence is not allowed here, e.g. escaping a newline
+    InvalidEscape(Position),
+
+    /// The lexer found something that isn't valid termpose
+    UnexpectedInput(Position),
+
+    /// A closing paren has no opening paren to match on its line
+    UnbalancedClose(Position),
+
+    /// A line's indent uses different whitespace to the level it's at or within
+    InconsistentIndent(Position),
+
+    /// A line is indented less than the line above, but not as deep as any line enclosing it
+    UnmatchedDedent(Position),
+
+    /// A line is indented deeper, but there's no line above it for it to belong to
+    UnexpectedIndent(Position),
+}
+
+impl Error {
+    /// Where in the source the error was found
+    pub fn position(&self) -> Position {
+        match *self {
+            Error::UnterminatedQuote(pos)
+            | Error::InvalidEscape(pos)
+            | Error::UnexpectedInput(pos)
+            | Error::UnbalancedClose(pos)
+            | Error::InconsistentIndent(pos)
+            | Error::UnmatchedDedent(pos)
+            | Error::UnexpectedIndent(pos) => pos,
+        }
+    }
+
+    /// What went wrong, without where
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Error::UnterminatedQuote(_) => "unterminated quote",
+            Error::InvalidEscape(_) => "invalid escape",
+            Error::UnexpectedInput(_) => "unexpected input",
+            Error::UnbalancedClose(_) => "unbalanced close paren",
+            Error::InconsistentIndent(_) => "inconsistent indent",
+            Error::UnmatchedDedent(_) => "dedent doesn't match any outer indent level",
+            Error::UnexpectedIndent(_) => "unexpected indent",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.summary(), self.position())
+    }
+}
+
+impl StdError for Error {}
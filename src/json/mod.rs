@@ -0,0 +1,264 @@
+//! Conversion between termpose trees and JSON
+//!
+//! There are two mappings. The head/tail mapping follows the s-list model of
+//! SPEC §1, and converts any tree to JSON and back exactly:
+//!
+//!  - a lone label, with nothing in its tail, is a string: `"a"`;
+//!  - any other s-list is an object with its head and its tail:
+//!    `{"head": "a", "tail": ["b", "c"]}`, where the head is a string for a
+//!    label, another s-list, or null for nil;
+//!  - a document, or any list of nodes, is an array.
+//!
+//! The natural mapping reads `key value` lines as objects, for documents which
+//! are written that way:
+//!
+//!  - nodes which all have a label head and a tail, with no label twice, are an
+//!    object, from each label to its tail;
+//!  - a tail which is a single label is that label's string;
+//!  - any other nodes are an array, where lone labels are strings, and other
+//!    s-lists are objects with their label as the only key;
+//!  - s-lists not headed by a label are written in the head/tail form.
+//!
+//! Going back from JSON, numbers and booleans become labels of their text, null
+//! an empty tail, and arrays in arrays s-lists headed by their first item. The
+//! natural mapping loses the difference between some trees, such as a tail of
+//! one label and a tail of one s-list, so isn't always reversible.
+
+use node::{Head, Node};
+use serde_json::{Map, Value};
+use std::error::Error as StdError;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// How trees are laid out in JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mapping {
+    /// Every s-list as its head and tail, exactly
+    HeadTail,
+
+    /// `key value` lines as objects
+    Natural,
+}
+
+/// JSON which doesn't describe a termpose tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// Where the problem is, as a JSON pointer
+    pub pointer: String,
+
+    /// What's wrong there
+    pub problem: &'static str,
+}
+
+impl JsonError {
+    fn new(pointer: &str, problem: &'static str) -> Self {
+        Self {
+            pointer: pointer.into(),
+            problem,
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "{} at the top level", self.problem)
+        } else {
+            write!(f, "{} at {}", self.problem, self.pointer)
+        }
+    }
+}
+
+impl StdError for JsonError {}
+
+/// Convert nodes to JSON
+pub fn to_json(nodes: &[Node], mapping: Mapping) -> Value {
+    match mapping {
+        Mapping::HeadTail => Value::Array(nodes.iter().map(head_tail).collect()),
+        Mapping::Natural => natural(nodes),
+    }
+}
+
+/// Convert JSON to nodes
+///
+/// A top-level array is a list of nodes, and anything else a single node; for
+/// the natural mapping, a top-level object is a node for each of its keys.
+///
+/// # Errors
+///
+/// If the JSON doesn't fit the mapping.
+pub fn from_json(value: &Value, mapping: Mapping) -> Result<Vec<Node<'static>>, JsonError> {
+    match (mapping, value) {
+        (Mapping::HeadTail, Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| from_head_tail(item, &format!("/{i}")))
+            .collect(),
+        (Mapping::HeadTail, value) => from_head_tail(value, "").map(|node| vec![node]),
+        (Mapping::Natural, value) => from_natural(value, ""),
+    }
+}
+
+fn label(name: &str) -> Node<'static> {
+    Node::new(name.to_owned(), "", 0)
+}
+
+fn head_tail(node: &Node) -> Value {
+    let head = match &node.head {
+        Some(Head::Label(name)) if node.children.is_empty() => {
+            return Value::String(name.to_string())
+        }
+        Some(Head::Label(name)) => Value::String(name.to_string()),
+        Some(Head::List(list)) => head_tail(list),
+        None => Value::Null,
+    };
+
+    let mut object = Map::new();
+    object.insert("head".into(), head);
+    object.insert(
+        "tail".into(),
+        Value::Array(node.children.iter().map(head_tail).collect()),
+    );
+    Value::Object(object)
+}
+
+fn from_head_tail(value: &Value, pointer: &str) -> Result<Node<'static>, JsonError> {
+    let object = match value {
+        Value::String(name) => return Ok(label(name)),
+        Value::Object(object) => object,
+        _ => return Err(JsonError::new(pointer, "expected a label or an s-list")),
+    };
+
+    if let Some(key) = object.keys().find(|key| *key != "head" && *key != "tail") {
+        let pointer = format!("{pointer}/{}", escape(key));
+        return Err(JsonError::new(&pointer, "unexpected key in an s-list"));
+    }
+
+    let head_pointer = format!("{pointer}/head");
+    let mut node = match object.get("head") {
+        None | Some(Value::Null) => Node::nil("", 0),
+        Some(Value::String(name)) => label(name),
+        Some(list @ Value::Object(_)) => Node::list(from_head_tail(list, &head_pointer)?, "", 0),
+        Some(_) => return Err(JsonError::new(&head_pointer, "expected a head")),
+    };
+
+    node.children = match object.get("tail") {
+        None | Some(Value::Null) => vec![],
+        Some(Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| from_head_tail(item, &format!("{pointer}/tail/{i}")))
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(JsonError::new(
+                &format!("{pointer}/tail"),
+                "expected a tail",
+            ))
+        }
+    };
+
+    Ok(node)
+}
+
+fn natural(nodes: &[Node]) -> Value {
+    let keyed = !nodes.is_empty()
+        && nodes.iter().enumerate().all(|(i, node)| match node.name() {
+            Some(name) => {
+                !node.children.is_empty()
+                    && !nodes.iter().take(i).any(|other| other.name() == Some(name))
+            }
+            None => false,
+        });
+
+    if keyed {
+        Value::Object(
+            nodes
+                .iter()
+                .map(|node| (node.name().unwrap_or_default().into(), natural_tail(node)))
+                .collect(),
+        )
+    } else {
+        Value::Array(nodes.iter().map(natural_item).collect())
+    }
+}
+
+fn natural_tail(node: &Node) -> Value {
+    match &node.children[..] {
+        [only] if only.is_label() => Value::String(only.name().unwrap_or_default().into()),
+        children => natural(children),
+    }
+}
+
+fn natural_item(node: &Node) -> Value {
+    match node.name() {
+        Some(name) if node.children.is_empty() => Value::String(name.into()),
+        Some(name) => {
+            let mut object = Map::new();
+            object.insert(name.into(), natural_tail(node));
+            Value::Object(object)
+        }
+        None => head_tail(node),
+    }
+}
+
+fn from_natural(value: &Value, pointer: &str) -> Result<Vec<Node<'static>>, JsonError> {
+    match value {
+        Value::Object(object) if is_head_tail(object) => {
+            from_head_tail(value, pointer).map(|node| vec![node])
+        }
+        Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| {
+                let mut node = label(key);
+                node.children = from_natural(value, &format!("{pointer}/{}", escape(key)))?;
+                Ok(node)
+            })
+            .collect(),
+        Value::Array(items) => {
+            let mut nodes = vec![];
+            for (i, item) in items.iter().enumerate() {
+                let pointer = format!("{pointer}/{i}");
+                match item {
+                    Value::Array(list) => nodes.push(natural_list(list, &pointer)?),
+                    Value::Null => nodes.push(Node::nil("", 0)),
+                    item => nodes.extend(from_natural(item, &pointer)?),
+                }
+            }
+            Ok(nodes)
+        }
+        Value::Null => Ok(vec![]),
+        Value::String(name) => Ok(vec![label(name)]),
+        Value::Number(number) => Ok(vec![label(&number.to_string())]),
+        Value::Bool(b) => Ok(vec![label(&b.to_string())]),
+    }
+}
+
+/// An array within an array: an s-list headed by its first item
+fn natural_list(items: &[Value], pointer: &str) -> Result<Node<'static>, JsonError> {
+    let mut nodes = from_natural(&Value::Array(items.to_vec()), pointer)?.into_iter();
+    let Some(first) = nodes.next() else {
+        return Ok(Node::nil("", 0));
+    };
+
+    let mut node = if first.is_label() {
+        first
+    } else {
+        Node::list(first, "", 0)
+    };
+    node.children.extend(nodes);
+    Ok(node)
+}
+
+/// Whether an object is an s-list in the head/tail form
+fn is_head_tail(object: &Map<String, Value>) -> bool {
+    object.contains_key("head")
+        && object.keys().all(|key| key == "head" || key == "tail")
+        && matches!(object.get("head"), Some(Value::Null | Value::Object(_)))
+}
+
+/// Escape a key for use in a JSON pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
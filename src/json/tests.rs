@@ -0,0 +1,150 @@
+use super::*;
+use parse;
+use printer::write;
+use serde_json::json;
+
+const MON: &str = "mon
+    name \"courageous leafward\"
+    stride 2
+    abilities
+        move
+        strike drain:2 effect(damage:2)
+";
+
+/// Print nodes back out, to compare trees without their positions
+fn print(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    write(&mut out, nodes).unwrap();
+    out
+}
+
+fn round_trip(input: &str, mapping: Mapping) -> String {
+    let json = to_json(&parse(input).unwrap(), mapping);
+    print(&from_json(&json, mapping).unwrap())
+}
+
+#[test]
+fn head_tail() {
+    assert_eq!(
+        to_json(&parse("a\n(b c) d\n()\ne:f\n").unwrap(), Mapping::HeadTail),
+        json!([
+            "a",
+            {"head": {"head": "b", "tail": ["c"]}, "tail": ["d"]},
+            {"head": null, "tail": []},
+            {"head": "e", "tail": ["f"]},
+        ])
+    );
+}
+
+#[test]
+fn head_tail_round_trips() {
+    for input in &[
+        MON,
+        "a\n",
+        "(a b) c d\n",
+        "((a b) c) d\n",
+        "a () (b ())\n",
+        "\"a b\" \"\"\n",
+        "a\n    \"\n        two\n        lines\n",
+    ] {
+        let canonical = print(&parse(input).unwrap());
+        assert_eq!(round_trip(input, Mapping::HeadTail), canonical);
+    }
+}
+
+#[test]
+fn head_tail_errors() {
+    let error = |value: Value| from_json(&value, Mapping::HeadTail).unwrap_err();
+
+    assert_eq!(
+        error(json!(["a", 2])),
+        JsonError::new("/1", "expected a label or an s-list")
+    );
+    assert_eq!(
+        error(json!({"head": "a", "tail": [{"head": 1}]})),
+        JsonError::new("/tail/0/head", "expected a head")
+    );
+    assert_eq!(
+        error(json!({"head": "a", "tail": "b"})),
+        JsonError::new("/tail", "expected a tail")
+    );
+    assert_eq!(
+        error(json!({"head": "a", "a/b": []})),
+        JsonError::new("/a~1b", "unexpected key in an s-list")
+    );
+    assert_eq!(
+        error(json!(true)).to_string(),
+        "expected a label or an s-list at the top level"
+    );
+}
+
+#[test]
+fn natural() {
+    assert_eq!(
+        to_json(&parse(MON).unwrap(), Mapping::Natural),
+        json!({
+            "mon": {
+                "name": "courageous leafward",
+                "stride": "2",
+                "abilities": [
+                    "move",
+                    {"strike": {"drain": "2", "effect": {"damage": "2"}}},
+                ],
+            },
+        })
+    );
+}
+
+#[test]
+fn natural_lists() {
+    assert_eq!(
+        to_json(&parse("a b\na c\n").unwrap(), Mapping::Natural),
+        json!([{"a": "b"}, {"a": "c"}])
+    );
+    assert_eq!(
+        to_json(&parse("a b c\n(d e) f\n").unwrap(), Mapping::Natural),
+        json!([
+            {"a": ["b", "c"]},
+            {"head": {"head": "d", "tail": ["e"]}, "tail": ["f"]},
+        ])
+    );
+}
+
+#[test]
+fn natural_round_trips() {
+    let canonical = print(&parse(MON).unwrap());
+    assert_eq!(round_trip(MON, Mapping::Natural), canonical);
+    assert_eq!(
+        round_trip("a b c\n(d e) f\n", Mapping::Natural),
+        "a\n    b\n    c\nd(e) f\n"
+    );
+}
+
+#[test]
+fn natural_from_json() {
+    let value = json!({
+        "name": "leafward",
+        "stride": 2,
+        "tame": false,
+        "owner": null,
+        "moves": [["strike", 2], [], "wait", null],
+    });
+
+    assert_eq!(
+        print(&from_json(&value, Mapping::Natural).unwrap()),
+        "name
+    leafward
+stride
+    2
+tame
+    false
+owner
+moves
+    strike
+        2
+    ()
+    wait
+    ()
+"
+    );
+}
@@ -1,16 +1,20 @@
-use nom::{self, Needed};
+use error::Error;
+use nom::{self, IResult, Needed};
+use position::{Locator, Position, Span};
+use std::borrow::Cow;
 use std::fmt;
 
 #[cfg(test)]
 mod tests;
 
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Indent(pub String);
+pub struct Indent<'a>(pub Cow<'a, str>);
 
+/// A label, borrowed from the source unless it had escapes to resolve
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Tag(pub String);
+pub struct Tag<'a>(pub Cow<'a, str>);
 
-impl fmt::Display for Tag {
+impl fmt::Display for Tag<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.0.fmt(f)
     }
@@ -29,51 +33,76 @@ pub enum Close {
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub enum Token {
-    Indent(Indent),
-    Tag(Tag),
+pub enum Token<'a> {
+    Indent(Indent<'a>),
+    Tag(Tag<'a>),
     Open(Open),
     Close(Close),
     Sigspace,
 }
 
-impl Token {
-    pub fn indent(i: &str) -> Self {
+impl<'a> Token<'a> {
+    pub fn indent(i: &'a str) -> Self {
         Indent(i.into()).into()
     }
 
     #[cfg(test)]
-    pub fn tag(t: &str) -> Self {
+    pub fn tag(t: &'a str) -> Self {
         Tag(t.into()).into()
     }
 }
 
-impl From<Indent> for Token {
-    fn from(i: Indent) -> Self {
+impl<'a> From<Indent<'a>> for Token<'a> {
+    fn from(i: Indent<'a>) -> Self {
         Token::Indent(i)
     }
 }
 
-impl From<Tag> for Token {
-    fn from(tag: Tag) -> Self {
+impl<'a> From<Tag<'a>> for Token<'a> {
+    fn from(tag: Tag<'a>) -> Self {
         Token::Tag(tag)
     }
 }
 
-impl From<Open> for Token {
+impl From<Open> for Token<'_> {
     fn from(o: Open) -> Self {
         Token::Open(o)
     }
 }
 
-impl From<Close> for Token {
+impl From<Close> for Token<'_> {
     fn from(c: Close) -> Self {
         Token::Close(c)
     }
 }
 
+/// A token, and where it is in the source
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Lexeme<'a> {
+    pub token: Token<'a>,
+    pub span: Span,
+}
+
+impl<'a> Lexeme<'a> {
+    pub fn new(token: Token<'a>, span: Span) -> Self {
+        Self { token, span }
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Line(pub Vec<Token>);
+pub struct Line<'a>(pub Vec<Lexeme<'a>>);
+
+impl<'a> Line<'a> {
+    /// The tokens on the line, without their spans
+    pub fn tokens(&self) -> impl Iterator<Item = &Token<'a>> {
+        self.0.iter().map(|lexeme| &lexeme.token)
+    }
+}
+
+/// A token as the parsers find it, between how much input is left before it
+/// and how much after; these are only turned into spans once lexing is done
+type Raw<'a> = (usize, Token<'a>, usize);
+type RawLine<'a> = Vec<Raw<'a>>;
 
 const NEWLINE: &str = "\r\n";
 const SPACING: &str = " \t";
@@ -81,17 +110,28 @@ const SPACING: &str = " \t";
 named!(newline<&str, &str>, is_a!(NEWLINE));
 named!(spacing<&str, &str>, is_a!(SPACING));
 
-named!(simple_tag<&str, Tag>, map!(
-    is_not!(" \t\r\n\":()"),
+named!(simple_tag<&str, Tag<'_>>, map!(
+    is_not!(" \t\r\n\"\\:()"),
     |tag| Tag(tag.into())
 ));
 
+// A bare label with no escapes, which can be borrowed as is
+named!(plain_tag<&str, Tag<'_>>, terminated!(simple_tag, not!(tag!("\\"))));
+
 named!(escaped_tag_inner<&str, &str>, alt!(complete!(is_not!("\"\\\r\n")) | eof!()));
-named!(escaped_tag<&str, Tag>, map!(
+named!(escaped_tag<&str, Tag<'_>>, map!(
     escaped_transform!(call!(escaped_tag_inner), '\\', escapes),
-    Tag
+    |tag| Tag(tag.into())
+));
+
+// Quoted text with no escapes, which can be borrowed as is
+named!(plain_quoted_tag<&str, Tag<'_>>, map!(
+    terminated!(is_not!("\"\\\r\n"), not!(tag!("\\"))),
+    |tag| Tag(tag.into())
 ));
 
+named!(quoted_text<&str, Tag<'_>>, alt!(plain_quoted_tag | escaped_tag));
+
 named!(escapes<&str, &str>, alt!(
     tag!("\\") => { |_| "\\" } |
     tag!("\"") => { |_| "\"" } |
@@ -99,49 +139,52 @@ named!(escapes<&str, &str>, alt!(
     tag!("r") => { |_| "\r" } |
     tag!("t") => { |_| "\t" } |
     tag!("h") => { |_| "☃" } |
-    take!(1) => { |s| s }
+    verify!(take!(1), |s: &str| !NEWLINE.contains(s)) => { |s| s }
 ));
 
 const BARE_ESCAPED_NOTS: &str = " \t\r\n\"\\:()";
 named!(bare_escaped_tag_inner<&str, &str>, alt!(complete!(is_not!(" \t\r\n\"\\:()")) | eof!()));
 named!(bare_escaped_str<&str, String>, escaped_transform!(call!(bare_escaped_tag_inner), '\\', escapes));
 
-named!(bare_escaped_tag<&str, Tag>, map!(
+named!(bare_escaped_tag<&str, Tag<'_>>, map!(
     do_parse!(
         first: none_of!(BARE_ESCAPED_NOTS) >>
         rest: bare_escaped_str >>
         (first, rest)
     ),
-    |(first, rest)| Tag(format!("{}{}", first, rest))
+    |(first, rest)| Tag(format!("{first}{rest}").into())
 ));
 
-named!(bare_escaped_tag_with_starting_escape<&str, Tag>, map!(
+named!(bare_escaped_tag_with_starting_escape<&str, Tag<'_>>, map!(
     do_parse!(
         tag!("\\") >>
         escape: escapes >>
         rest: opt!(alt!(bare_escaped_tag | bare_escaped_tag_with_starting_escape)) >>
         (escape, rest)
     ),
-    |(escape, rest)| Tag(format!("{}{}", escape, rest.unwrap_or_else(|| Tag("".into()))))
+    |(escape, rest)| Tag(format!("{escape}{}", rest.unwrap_or_default()).into())
 ));
 
-named!(quoted_tag<&str, Tag>, delimited!(
+named!(quoted_tag<&str, Tag<'_>>, delimited!(
     tag!("\""),
-    escaped_tag,
+    quoted_text,
     tag!("\"")
 ));
 
-named!(a_tag<&str, Tag>, alt!(
+named!(a_tag<&str, Tag<'_>>, alt!(
     quoted_tag |
     bare_escaped_tag_with_starting_escape |
+    plain_tag |
     bare_escaped_tag |
     simple_tag
 ));
 
-named!(multitag<&str, Vec<Tag> >, many1!(do_parse!(
+named!(multitag<&str, RawLine<'_> >, many1!(do_parse!(
     eat_separator!(SPACING) >>
+    start: remaining >>
     tag: a_tag >>
-    (tag)
+    end: remaining >>
+    ((start, tag.into(), end))
 )));
 
 named!(open<&str, Open>, alt!(
@@ -151,24 +194,29 @@ named!(open<&str, Open>, alt!(
 
 named!(close<&str, Close>, map!(tag!(")"), |_| Close::Paren));
 
-named!(line<&str, Line>, map!(do_parse!(
+named!(line<&str, RawLine<'_> >, map!(do_parse!(
+    istart: remaining >>
     i: eat_separator!(SPACING) >>
+    iend: remaining >>
     toks: many0!(alt!(
-        multitag => { |mt: Vec<Tag>| mt.into_iter().map(Token::Tag).collect() } |
-        open => { |o: Open| vec![o.into()] } |
-        close => { |c: Close| vec![c.into()] } |
-        spacing => { |_| vec![Token::Sigspace] }
+        multitag |
+        do_parse!(s: remaining >> o: open >> e: remaining >> (vec![(s, o.into(), e)])) |
+        do_parse!(s: remaining >> c: close >> e: remaining >> (vec![(s, c.into(), e)])) |
+        do_parse!(s: remaining >> spacing >> e: remaining >> (vec![(s, Token::Sigspace, e)]))
     )) >>
     trailq: opt!(do_parse!(
+        qstart: remaining >>
         tag!("\"") >>
-        s: opt!(escaped_tag) >>
-        (s)
+        qend: remaining >>
+        s: opt!(quoted_text) >>
+        send: remaining >>
+        (qstart, qend, s, send)
     )) >>
-    (i, toks, trailq)
-), |(i, aroar, trailq)| {
+    ((istart, i, iend), toks, trailq)
+), |((istart, i, iend), aroar, trailq)| {
     let mut toks = vec![];
 
-    toks.push(Token::indent(i));
+    toks.push((istart, Token::indent(i), iend));
 
     for ar in aroar {
         for tok in ar {
@@ -176,40 +224,222 @@ named!(line<&str, Line>, map!(do_parse!(
         }
     }
 
-    if let Some(q) = trailq {
-        toks.push(Open::Quote.into());
+    if let Some((qstart, qend, q, send)) = trailq {
+        toks.push((qstart, Open::Quote.into(), qend));
         if let Some(t) = q {
             if !t.0.is_empty() {
-                toks.push(t.into());
+                toks.push((qend, t.into(), send));
             }
         }
     }
 
-    Line(toks)
+    toks
 }));
 
-named!(lines<&str, Vec<Line> >, map!(do_parse!(
-    nls: many0!(alt!(tag!("\r") | tag!("\r\n") | tag!("\n"))) >>
-    lines: separated_list_complete!(newline, line) >>
-    (nls, lines)
-), |(nls, mut lines)| {
-    for _ in 0..nls.len() {
-        lines.insert(0, Line(vec![]));
+/// Doesn't consume anything, but returns how much input is left
+///
+/// This is used to find out where things are, as the distance to the end of the
+/// input doesn't depend on where the input started.
+#[allow(clippy::unnecessary_wraps)]
+fn remaining(input: &str) -> IResult<&str, usize> {
+    Ok((input, input.len()))
+}
+
+named!(located_line<&str, (usize, RawLine<'_>)>, do_parse!(
+    at: remaining >>
+    line: line >>
+    (at, line)
+));
+
+named!(single_newline<&str, &str>, alt!(
+    complete!(tag!("\r\n")) |
+    complete!(tag!("\n")) |
+    complete!(tag!("\r"))
+));
+
+/// One line of a multiline label
+///
+/// It must be blank, or indented deeper than the line which opened the label.
+/// Either way it is kept raw, as an Indent and (if not blank) a Tag of the rest.
+fn quoted_line<'a>(input: &'a str, opener: &str) -> IResult<&'a str, RawLine<'a>> {
+    let Some(end) = input.find(|c| NEWLINE.contains(c)) else {
+        return Err(nom::Err::Incomplete(Needed::Unknown));
+    };
+
+    let (text, rest) = input.split_at(end);
+    let content = text.trim_start_matches(|c| SPACING.contains(c));
+    #[allow(clippy::indexing_slicing)]
+    let indent = &text[..text.len() - content.len()];
+
+    let deeper = indent.len() > opener.len() && indent.starts_with(opener);
+    let content_start = input.len() - indent.len();
+    let indent = (input.len(), Token::indent(indent), content_start);
+
+    if content.is_empty() {
+        Ok((rest, vec![indent]))
+    } else if deeper {
+        let tag = (content_start, Tag(content.into()).into(), rest.len());
+        Ok((rest, vec![indent, tag]))
+    } else {
+        Err(nom::Err::Error(error_position!(
+            input,
+            nom::ErrorKind::Custom(0)
+        )))
     }
+}
+
+/// The lines of a multiline label, if `opener` ends with an open quote
+fn quoted_lines<'a>(
+    input: &'a str,
+    opener: &RawLine,
+) -> IResult<&'a str, Vec<(usize, RawLine<'a>)>> {
+    let quoted = opener.iter().any(|(_, t, _)| *t == Open::Quote.into());
+    let indent = match opener.first() {
+        Some((_, Token::Indent(i), _)) if quoted => &i.0,
+        _ => return Ok((input, vec![])),
+    };
+
+    many0!(input, complete!(do_parse!(
+        single_newline >>
+        at: remaining >>
+        line: call!(quoted_line, indent) >>
+        (at, line)
+    )))
+}
+
+named!(located_block<&str, Vec<(usize, RawLine<'_>)> >, do_parse!(
+    first: located_line >>
+    quoted: call!(quoted_lines, &first.1) >>
+    (Some(first).into_iter().chain(quoted).collect())
+));
 
-    lines
+named!(located_lines<&str, Vec<(usize, RawLine<'_>)> >, map!(do_parse!(
+    nls: many0!(do_parse!(
+        at: remaining >>
+        alt!(tag!("\r") | tag!("\r\n") | tag!("\n")) >>
+        (at)
+    )) >>
+    blocks: separated_list_complete!(newline, located_block) >>
+    (nls, blocks)
+), |(nls, blocks): (Vec<usize>, Vec<Vec<(usize, RawLine)>>)| {
+    nls.into_iter()
+        .map(|at| (at, vec![]))
+        .chain(blocks.into_iter().flatten())
+        .collect()
 }));
 
-named!(termpose<&str, Vec<Line> >, complete!(lines));
+named!(termpose<&str, Vec<(usize, RawLine<'_>)> >, complete!(located_lines));
+
+/// Lex a whole termpose document into lines of tokens
+///
+/// # Errors
+///
+/// If the input contains something that isn't valid termpose.
+pub fn lex(input: &str) -> Result<Vec<Line<'_>>, Error> {
+    Ok(lex_located(input)?
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect())
+}
 
-pub fn lex(input: &str) -> Result<Vec<Line>, nom::Err<&str>> {
-    if !input.ends_with('\n') {
-        return Err(nom::Err::Incomplete(Needed::Size(1)));
+/// Lex a whole termpose document into lines of tokens, with where each line starts
+///
+/// # Errors
+///
+/// If the input contains something that isn't valid termpose.
+pub fn lex_located(input: &str) -> Result<Vec<(Position, Line<'_>)>, Error> {
+    // Nothing but line endings has no lines worth lexing
+    if input.chars().all(|c| NEWLINE.contains(c)) {
+        return Ok(vec![]);
     }
 
-    let (rest, done) = termpose(input)?;
-    if rest != "\n" {
-        unreachable!()
+    if input.ends_with(|c| NEWLINE.contains(c)) {
+        return lex_ended(input, input);
     }
-    Ok(done)
+
+    // The lexer can only tell a line is over when it sees the newline, so lex
+    // a copy with one added and point what's borrowed from it back at the input
+    let ended = format!("{input}\n");
+    Ok(lex_ended(input, &ended)?
+        .into_iter()
+        .map(|(pos, line)| (pos, rebase(line, &ended, input)))
+        .collect())
+}
+
+/// Lex `text`, which is `source` with a newline at the end if it lacked one
+fn lex_ended<'a>(source: &str, text: &'a str) -> Result<Vec<(Position, Line<'a>)>, Error> {
+    let locator = Locator::new(source);
+    let at = |rest: &str| locator.locate(text.len() - rest.len());
+
+    let (rest, done) = termpose(text).map_err(|err| {
+        Error::UnexpectedInput(match err {
+            nom::Err::Error(nom::Context::Code(rest, _))
+            | nom::Err::Failure(nom::Context::Code(rest, _)) => at(rest),
+            _ => at(text),
+        })
+    })?;
+
+    // The list of lines stops before the separator of the first line it
+    // can't lex, so skip that and find out how far into the line we can get
+    let rest = rest.trim_start_matches(|c| NEWLINE.contains(c));
+    if !rest.is_empty() {
+        let rest = match line(rest) {
+            Ok((rest, _))
+            | Err(
+                nom::Err::Error(nom::Context::Code(rest, _))
+                | nom::Err::Failure(nom::Context::Code(rest, _)),
+            ) => rest,
+            _ => rest,
+        };
+
+        return Err(if rest.starts_with('\\') {
+            Error::InvalidEscape(at(rest))
+        } else if rest.starts_with('"') {
+            Error::UnterminatedQuote(at(rest))
+        } else {
+            Error::UnexpectedInput(at(rest))
+        });
+    }
+
+    let span = |(start, token, end): Raw<'a>| {
+        Lexeme::new(token, locator.span(text.len() - start, text.len() - end))
+    };
+
+    Ok(done
+        .into_iter()
+        .map(|(left, line)| {
+            let line = Line(line.into_iter().map(span).collect());
+            (locator.locate(text.len() - left), line)
+        })
+        .collect())
+}
+
+/// Point text borrowed from `from` at the same place in `to`
+fn rebase<'a>(line: Line, from: &str, to: &'a str) -> Line<'a> {
+    let rebase_str = |text: Cow<str>| -> Cow<'a, str> {
+        match text {
+            Cow::Borrowed(s) => {
+                let start = s.as_ptr() as usize - from.as_ptr() as usize;
+                to.get(start..start + s.len())
+                    .map_or_else(|| s.to_owned().into(), Cow::Borrowed)
+            }
+            Cow::Owned(s) => Cow::Owned(s),
+        }
+    };
+
+    Line(
+        line.0
+            .into_iter()
+            .map(|Lexeme { token, span }| {
+                let token = match token {
+                    Token::Indent(Indent(i)) => Indent(rebase_str(i)).into(),
+                    Token::Tag(Tag(t)) => Tag(rebase_str(t)).into(),
+                    Token::Open(o) => o.into(),
+                    Token::Close(c) => c.into(),
+                    Token::Sigspace => Token::Sigspace,
+                };
+                Lexeme::new(token, span)
+            })
+            .collect(),
+    )
 }
@@ -1,4 +1,58 @@
 use super::*;
+use std::ops::Range;
+
+// Most tests only care about the tokens, so these shadow the lexer's own
+// types and parsers with versions that leave the spans out.
+
+#[derive(Clone, Debug, PartialEq)]
+struct Line<'a>(Vec<Token<'a>>);
+
+impl<'a> From<RawLine<'a>> for Line<'a> {
+    fn from(raw: RawLine<'a>) -> Self {
+        Line(raw.into_iter().map(|(_, token, _)| token).collect())
+    }
+}
+
+impl<'a> From<super::Line<'a>> for Line<'a> {
+    fn from(line: super::Line<'a>) -> Self {
+        Line(line.0.into_iter().map(|lexeme| lexeme.token).collect())
+    }
+}
+
+fn multitag(input: &str) -> IResult<&str, Vec<Tag<'_>>> {
+    super::multitag(input).map(|(rest, raw)| {
+        let tags = raw
+            .into_iter()
+            .filter_map(|(_, token, _)| match token {
+                Token::Tag(tag) => Some(tag),
+                _ => None,
+            })
+            .collect();
+        (rest, tags)
+    })
+}
+
+fn line(input: &str) -> IResult<&str, Line<'_>> {
+    super::line(input).map(|(rest, raw)| (rest, raw.into()))
+}
+
+fn lines(input: &str) -> IResult<&str, Vec<Line<'_>>> {
+    located_lines(input)
+        .map(|(rest, raw)| (rest, raw.into_iter().map(|(_, line)| line.into()).collect()))
+}
+
+fn lex(input: &str) -> Result<Vec<Line<'_>>, Error> {
+    super::lex(input).map(|lines| lines.into_iter().map(Line::from).collect())
+}
+
+fn lex_located(input: &str) -> Result<Vec<(Position, Line<'_>)>, Error> {
+    super::lex_located(input).map(|lines| {
+        lines
+            .into_iter()
+            .map(|(pos, line)| (pos, line.into()))
+            .collect()
+    })
+}
 
 #[test]
 fn simple_tag_partial_read() {
@@ -1217,12 +1271,44 @@ fn trailing_quote_and_quote_in_indent() {
                     Token::tag("open"),
                     Open::Quote.into(),
                 ]),
+                Line(vec![Token::indent("\t"), Token::tag("home\"auction")]),
+            ]
+        ))
+    );
+}
+
+#[test]
+fn multiline_keeps_raw_text() {
+    assert_eq!(
+        lines("a \"\n  (b:  \"c\\n\" \n\n    d\ne\n"),
+        Ok((
+            "\n",
+            vec![
                 Line(vec![
-                    Token::indent("\t"),
-                    Token::tag("home"),
+                    Token::indent(""),
+                    Token::tag("a"),
+                    Token::Sigspace,
                     Open::Quote.into(),
-                    Token::tag("auction"),
                 ]),
+                Line(vec![Token::indent("  "), Token::tag("(b:  \"c\\n\" ")]),
+                Line(vec![Token::indent("")]),
+                Line(vec![Token::indent("    "), Token::tag("d")]),
+                Line(vec![Token::indent(""), Token::tag("e")]),
+            ]
+        ))
+    );
+}
+
+#[test]
+fn multiline_ends_at_same_indent() {
+    assert_eq!(
+        lines("  a\"\n    b\n  c \"d\"\n"),
+        Ok((
+            "\n",
+            vec![
+                Line(vec![Token::indent("  "), Token::tag("a"), Open::Quote.into()]),
+                Line(vec![Token::indent("    "), Token::tag("b")]),
+                Line(vec![Token::indent("  "), Token::tag("c"), Token::tag("d")]),
             ]
         ))
     );
@@ -1230,10 +1316,96 @@ fn trailing_quote_and_quote_in_indent() {
 
 #[test]
 fn lex_missing_trailing_newline() {
-    assert_eq!(lex("."), Err(nom::Err::Incomplete(Needed::Size(1))));
+    assert_eq!(
+        lex("a\n."),
+        Ok(vec![
+            Line(vec![Token::indent(""), Token::tag("a")]),
+            Line(vec![Token::indent(""), Token::tag(".")]),
+        ])
+    );
+}
+
+#[test]
+fn lex_cr_only() {
+    let expected = vec![
+        Line(vec![Token::indent(""), Token::tag("a")]),
+        Line(vec![Token::indent(""), Token::tag("b")]),
+    ];
+    assert_eq!(lex("a\rb\r"), Ok(expected.clone()));
+    assert_eq!(lex("a\rb"), Ok(expected));
+}
+
+#[test]
+fn lex_empty() {
+    assert_eq!(lex(""), Ok(vec![]));
+}
+
+#[test]
+fn lex_only_line_endings() {
+    assert_eq!(lex("\n"), Ok(vec![]));
+    assert_eq!(lex("\r\n\n"), Ok(vec![]));
+}
+
+#[test]
+fn lex_error_on_unended_last_line() {
+    assert_eq!(
+        lex("a\nb\\"),
+        Err(Error::InvalidEscape(Position::new(2, 2, 3)))
+    );
+}
+
+#[test]
+fn unended_last_line_still_borrows() {
+    let lines = lex("a\nlast").unwrap();
+    assert!(matches!(
+        lines[1].0[1],
+        Token::Tag(Tag(Cow::Borrowed("last")))
+    ));
+}
+
+#[test]
+fn lex_crlf() {
+    assert_eq!(
+        lex("a\r\nb\r\n"),
+        Ok(vec![
+            Line(vec![Token::indent(""), Token::tag("a")]),
+            Line(vec![Token::indent(""), Token::tag("b")]),
+        ])
+    );
+}
+
+#[test]
+fn lex_escaped_newline() {
+    assert_eq!(
+        lex("foo\n  bar\\\n"),
+        Err(Error::InvalidEscape(Position::new(2, 6, 9)))
+    );
+}
+
+#[test]
+fn lex_located_blank_lines() {
+    assert_eq!(
+        lex_located("\na\n\n\n  b\r\nc\n"),
+        Ok(vec![
+            (Position::new(1, 1, 0), Line(vec![])),
+            (
+                Position::new(2, 1, 1),
+                Line(vec![Token::indent(""), Token::tag("a")])
+            ),
+            (
+                Position::new(5, 1, 5),
+                Line(vec![Token::indent("  "), Token::tag("b")])
+            ),
+            (
+                Position::new(6, 1, 10),
+                Line(vec![Token::indent(""), Token::tag("c")])
+            ),
+        ])
+    );
 }
 
 #[test]
+#[allow(clippy::too_many_lines)]
 fn lex_example() {
     assert_eq!(
         lex("
@@ -1274,21 +1446,12 @@ mon
             ]),
             Line(vec![
                 Token::indent("    "),
-                Token::tag("Plants"),
-                Token::tag("healing"),
-                Token::tag("bombs."),
+                Token::tag("Plants healing bombs."),
             ]),
+            Line(vec![Token::indent("    "), Token::tag("Standard attack.")]),
             Line(vec![
                 Token::indent("    "),
-                Token::tag("Standard"),
-                Token::tag("attack."),
-            ]),
-            Line(vec![
-                Token::indent("    "),
-                Token::tag("Watch"),
-                Token::tag("out,"),
-                Token::tag("it's"),
-                Token::tag("fragile!"),
+                Token::tag("Watch out, it's fragile!"),
             ]),
             Line(vec![
                 Token::indent("  "),
@@ -1353,3 +1516,63 @@ mon
         ])
     );
 }
+
+#[test]
+fn labels_borrow_unless_escaped() {
+    let borrowed = |t: &Token| {
+        matches!(
+            t,
+            Token::Tag(Tag(Cow::Borrowed(_))) | Token::Indent(Indent(Cow::Borrowed(_)))
+        )
+    };
+
+    let lines = lex("  plain \"quoted text\" esc\\:aped \"esc\\\"aped\" \"trailing\n").unwrap();
+    let toks = &lines[0].0;
+    assert_eq!(
+        toks.iter()
+            .filter(|t| !matches!(t, Token::Sigspace | Token::Open(_)))
+            .map(borrowed)
+            .collect::<Vec<_>>(),
+        vec![true, true, true, false, false, true]
+    );
+}
+
+fn ranges(input: &str) -> Vec<Vec<Range<usize>>> {
+    super::lex(input)
+        .unwrap()
+        .into_iter()
+        .map(|line| line.0.iter().map(|lexeme| lexeme.span.range()).collect())
+        .collect()
+}
+
+#[test]
+fn token_spans() {
+    assert_eq!(
+        ranges("ab (c)\n"),
+        vec![vec![0..0, 0..2, 2..3, 3..4, 4..5, 5..6]]
+    );
+    assert_eq!(ranges("x \"y z\"\n"), vec![vec![0..0, 0..1, 2..7]]);
+    assert_eq!(ranges("x \"ab\n"), vec![vec![0..0, 0..1, 1..2, 2..3, 3..5]]);
+    assert_eq!(ranges("a\nbc"), vec![vec![0..0, 0..1], vec![2..2, 2..4]]);
+}
+
+#[test]
+fn token_spans_count_columns_in_chars() {
+    let lines = super::lex("  é \"\n    uu\n").unwrap();
+    let spans: Vec<Span> = lines
+        .iter()
+        .flat_map(|line| line.0.iter().map(|lexeme| lexeme.span))
+        .collect();
+
+    assert_eq!(
+        spans,
+        vec![
+            Span::new(Position::new(1, 1, 0), Position::new(1, 3, 2)),
+            Span::new(Position::new(1, 3, 2), Position::new(1, 4, 4)),
+            Span::new(Position::new(1, 4, 4), Position::new(1, 5, 5)),
+            Span::new(Position::new(1, 5, 5), Position::new(1, 6, 6)),
+            Span::new(Position::new(2, 1, 7), Position::new(2, 5, 11)),
+            Span::new(Position::new(2, 5, 11), Position::new(2, 7, 13)),
+        ]
+    );
+}
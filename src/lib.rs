@@ -0,0 +1,154 @@
+//! Two-pass [termpose] parser.
+//!
+//! 1. Lex into token lists, with [`lexer::lex`].
+//! 2. Parse into tree(s), with [`Termpose`].
+//!
+//! Tools which need to write the source back as it was can use the lossless
+//! [`cst`] instead, or edit it in place with an [`Editor`].
+//!
+//! Most users will only need [`parse`], which does both:
+//!
+//! ```
+//! let nodes = nompose::parse("mon\n    name leafward\n").unwrap();
+//! assert_eq!(nodes[0].name(), Some("mon"));
+//! assert_eq!(nodes[0].children[0].name(), Some("name"));
+//! assert_eq!(nodes[0].children[0].children[0].name(), Some("leafward"));
+//! ```
+//!
+//! Trees can be written back out as canonical termpose, with [`write()`] or
+//! through their `Display` impls, or in other styles with [`Style`]:
+//!
+//! ```
+//! let nodes = nompose::parse("mon name:leafward\n").unwrap();
+//! assert_eq!(nodes[0].to_string(), "mon\n    name\n        leafward\n");
+//!
+//! let colons = nompose::Style::inline().with_nesting(nompose::Nesting::Colons);
+//! assert_eq!(colons.print(&nodes), "mon name:leafward\n");
+//! ```
+//!
+//! Nodes can be walked over with [`Node::depth_first`], [`Node::breadth_first`],
+//! or a [`Visitor`], and looked up by path, with [`Node::get`] or a [`Query`]:
+//!
+//! ```
+//! let nodes = nompose::parse("mon\n    abilities\n        strike drain:2\n").unwrap();
+//! let drain = nodes[0].get("abilities/strike/drain").unwrap();
+//! assert_eq!(drain.children[0].name(), Some("2"));
+//! ```
+//!
+//! Labels are left as text, but `key value` nodes can be read as Rust values
+//! with [`Node::parse`], [`Node::as_i64`] and the like, or [`Node::value`]:
+//!
+//! ```
+//! let nodes = nompose::parse("mon\n    health 50\n").unwrap();
+//! assert_eq!(nodes[0].get("health").unwrap().as_i64(), Ok(50));
+//! assert_eq!(nodes[0].value::<u32>("health"), Ok(50));
+//! ```
+//!
+//! With the `json` feature, on by default, trees can also be converted to and
+//! from JSON with the [`json`] module. With the `serde` feature, also on by
+//! default, Rust values can be read straight from termpose with [`de`], and
+//! written back out with [`ser`]:
+//!
+#![cfg_attr(
+    feature = "serde",
+    doc = r#"
+```
+#[macro_use]
+extern crate serde_derive;
+extern crate nompose;
+
+#[derive(Deserialize, Serialize)]
+struct Mon {
+    name: String,
+    health: u32,
+    abilities: Vec<String>,
+}
+
+# fn main() {
+let mon: Mon = nompose::de::from_str("name leafward\nhealth 50\nabilities move strike\n").unwrap();
+assert_eq!(mon.name, "leafward");
+assert_eq!(mon.health, 50);
+assert_eq!(mon.abilities, ["move", "strike"]);
+
+let text = nompose::ser::to_string(&mon).unwrap();
+assert_eq!(text, "name leafward\nhealth 50\nabilities move strike\n");
+# }
+```
+"#
+)]
+//!
+//! [termpose]: https://github.com/makoConstruct/termpose
+
+#![forbid(unsafe_code)]
+#![deny(clippy::all, clippy::pedantic)]
+#![allow(clippy::must_use_candidate)]
+
+#[macro_use]
+extern crate nom;
+#[cfg(feature = "derive")]
+#[allow(unused_imports)]
+#[macro_use]
+extern crate nompose_derive;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature = "json")]
+extern crate serde_json;
+
+pub use convert::{write_value, DecodeError, FromTermpose, ToTermpose};
+pub use document::Document;
+pub use edit::{EditError, Editor};
+pub use error::Error;
+pub use node::{Head, Node};
+pub use parser::{Termpose, Trace};
+pub use position::{Position, Span};
+pub use printer::{write, Layout, Nesting, Style};
+pub use query::{Query, QueryError};
+pub use value::ValueError;
+pub use walk::{BreadthFirst, DepthFirst, Order, Visitor, VisitorMut, Walked};
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub use nompose_derive::*;
+
+pub mod convert;
+pub mod cst;
+#[cfg(feature = "serde")]
+pub mod de;
+mod document;
+mod edit;
+mod error;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lexer;
+mod node;
+mod parser;
+mod position;
+mod printer;
+mod query;
+#[cfg(feature = "serde")]
+pub mod ser;
+mod value;
+mod walk;
+
+/// Parse a termpose document into its top-level nodes
+///
+/// # Errors
+///
+/// If the input cannot be lexed, or its tokens cannot be assembled into a tree.
+pub fn parse(input: &str) -> Result<Vec<Node<'_>>, Error> {
+    parse_document(input).map(|doc| doc.children)
+}
+
+/// Parse a termpose document, keeping hold of where it is in the input
+///
+/// # Errors
+///
+/// If the input cannot be lexed, or its tokens cannot be assembled into a tree.
+pub fn parse_document(input: &str) -> Result<Document<'_>, Error> {
+    let mut pose = Termpose::new_from_str(input)?;
+    while pose.turn()? {}
+    Ok(pose.finalise())
+}
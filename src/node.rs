@@ -0,0 +1,130 @@
+use position::{Position, Span};
+use std::borrow::Cow;
+
+/// The head of an s-list: either a label or another s-list
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub enum Head<'a> {
+    Label(Cow<'a, str>),
+    List(Box<Node<'a>>),
+}
+
+impl Head<'_> {
+    /// Copy anything borrowed, so the head no longer depends on the source
+    pub fn into_owned(self) -> Head<'static> {
+        match self {
+            Head::Label(label) => Head::Label(label.into_owned().into()),
+            Head::List(list) => Head::List(Box::new(list.into_owned())),
+        }
+    }
+}
+
+/// An s-list
+///
+/// The `children` are its tail. Only nil has no head.
+///
+/// Labels and indents borrow from the source where they can, so a tree can't
+/// outlive it; use [`Node::into_owned`] to keep it for longer.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Node<'a> {
+    pub head: Option<Head<'a>>,
+
+    /// How much deeper this node's line is indented than its parent's
+    pub indent: Cow<'a, str>,
+    pub line: usize,
+
+    /// Where the head is in the source: the label, or the whole of a head s-list
+    pub head_span: Span,
+
+    /// Where the whole s-list is in the source, including its tail
+    pub span: Span,
+
+    pub children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    /// Create a node for a label found on `line`
+    pub fn new<N, I>(name: N, indent: I, line: usize) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+        I: Into<Cow<'a, str>>,
+    {
+        Self::with_head(Some(Head::Label(name.into())), indent.into(), line)
+    }
+
+    /// Create a node headed by another s-list
+    ///
+    /// The node starts out spanning the head.
+    pub fn list<I: Into<Cow<'a, str>>>(head: Node<'a>, indent: I, line: usize) -> Self {
+        let span = head.span;
+        Self::with_head(Some(Head::List(Box::new(head))), indent.into(), line).with_span(span)
+    }
+
+    /// Create the nil s-list, with no head and an empty tail
+    pub fn nil<I: Into<Cow<'a, str>>>(indent: I, line: usize) -> Self {
+        Self::with_head(None, indent.into(), line)
+    }
+
+    fn with_head(head: Option<Head<'a>>, indent: Cow<'a, str>, line: usize) -> Self {
+        Self {
+            head,
+            indent,
+            line,
+            head_span: Span::default(),
+            span: Span::default(),
+            children: Vec::with_capacity(0),
+        }
+    }
+
+    /// Set where both the head and the whole node are in the source
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.head_span = span;
+        self.span = span;
+        self
+    }
+
+    /// Stretch the node's span to reach at least as far as `end`
+    pub fn extend_to(&mut self, end: Position) {
+        if end > self.span.end {
+            self.span.end = end;
+        }
+    }
+
+    /// The label at the head, if the head is a label
+    pub fn name(&self) -> Option<&str> {
+        match &self.head {
+            Some(Head::Label(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The s-list at the head, if the head is an s-list
+    pub fn head_list(&self) -> Option<&Node<'a>> {
+        match &self.head {
+            Some(Head::List(list)) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a label on its own, with nothing in its tail
+    pub fn is_label(&self) -> bool {
+        self.name().is_some() && self.children.is_empty()
+    }
+
+    /// Whether this is the nil s-list
+    pub fn is_nil(&self) -> bool {
+        self.head.is_none() && self.children.is_empty()
+    }
+
+    /// Copy anything borrowed, so the tree no longer depends on the source
+    pub fn into_owned(self) -> Node<'static> {
+        Node {
+            head: self.head.map(Head::into_owned),
+            indent: self.indent.into_owned().into(),
+            line: self.line,
+            head_span: self.head_span,
+            span: self.span,
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use node::Node;
+
+/// Where a node is in an Arena
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug)]
+struct Slot<'a> {
+    node: Node<'a>,
+    children: Vec<NodeId>,
+    parent: Option<NodeId>,
+}
+
+/// A tree under construction, kept flat and linked by index
+///
+/// Nodes are only ever added, below nodes already there, so every node comes
+/// after its parent. That lets the tree be put together in a single backwards
+/// pass at the end, moving each node into its parent.
+///
+/// The root only holds the top-level nodes, and isn't part of the tree.
+#[derive(Clone, Debug)]
+pub struct Arena<'a> {
+    slots: Vec<Slot<'a>>,
+}
+
+impl Default for Arena<'_> {
+    fn default() -> Self {
+        Self {
+            slots: vec![Slot {
+                node: Node::nil("", 0),
+                children: vec![],
+                parent: None,
+            }],
+        }
+    }
+}
+
+impl<'a> Arena<'a> {
+    pub fn root() -> NodeId {
+        NodeId(0)
+    }
+
+    /// Add a node as the last child of `parent`
+    pub fn add(&mut self, parent: NodeId, node: Node<'a>) -> NodeId {
+        let id = NodeId(self.slots.len());
+        self.slots.push(Slot {
+            node,
+            children: vec![],
+            parent: Some(parent),
+        });
+
+        if let Some(slot) = self.slots.get_mut(parent.0) {
+            slot.children.push(id);
+        }
+
+        id
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.slots.get(id.0)?.children.last().copied()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slots.get(id.0)?.parent
+    }
+
+    /// Put the tree together, returning the top-level nodes
+    ///
+    /// Children added here come after any a node already had, and each node's
+    /// span is stretched to cover its children.
+    pub fn into_tree(self) -> Vec<Node<'a>> {
+        let mut built: Vec<Option<Node<'a>>> = Vec::with_capacity(self.slots.len());
+        built.resize_with(self.slots.len(), || None);
+
+        for (i, slot) in self.slots.into_iter().enumerate().rev() {
+            let mut node = slot.node;
+            node.children.extend(
+                slot.children
+                    .iter()
+                    .filter_map(|child| built.get_mut(child.0).and_then(Option::take)),
+            );
+            if let Some(end) = node.children.last().map(|child| child.span.end) {
+                node.extend_to(end);
+            }
+
+            if let Some(place) = built.get_mut(i) {
+                *place = Some(node);
+            }
+        }
+
+        built
+            .into_iter()
+            .next()
+            .and_then(|root| root)
+            .map(|root| root.children)
+            .unwrap_or_default()
+    }
+}
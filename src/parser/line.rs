@@ -0,0 +1,196 @@
+use node::Node;
+use position::{Position, Span};
+use std::borrow::Cow;
+
+/// The kinds of group which can be open within a line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Group {
+    /// The line itself, which is only closed by its end
+    Line,
+
+    /// Opened by a paren, and closed by the matching paren
+    Paren,
+
+    /// Opened by a colon, and closed by the next sibling, a paren, or the line's end
+    Colon,
+}
+
+/// A group of items being built up within a line
+#[derive(Clone, Debug)]
+struct Open<'a> {
+    group: Group,
+
+    /// Whether the items go on the end of the tail of the item just before the
+    /// group was opened, rather than forming an s-list of their own
+    tail: bool,
+
+    /// Where the paren or colon which opened the group starts
+    start: Position,
+
+    items: Vec<Node<'a>>,
+}
+
+/// Builds the nodes of a single line
+///
+/// Labels, parens, colons and spaces are fed in as they come. Parens and colons
+/// directly following an item open that item's tail, so `a(b c)` and `a:b` are
+/// `(a b c)` and `(a b)`; otherwise they start a new s-list, headed by the
+/// first item within. A colon's group ends as soon as the next sibling starts.
+#[derive(Clone, Debug)]
+pub struct LineBuilder<'a> {
+    indent: Cow<'a, str>,
+    line: usize,
+
+    /// Open groups, innermost last; the first is always the line's own
+    open: Vec<Open<'a>>,
+
+    /// Whether the last thing fed in was an item, with nothing in between
+    adjacent: bool,
+}
+
+impl<'a> LineBuilder<'a> {
+    pub fn new(indent: Cow<'a, str>, line: usize) -> Self {
+        Self {
+            indent,
+            line,
+            open: vec![Open {
+                group: Group::Line,
+                tail: false,
+                start: Position::default(),
+                items: vec![],
+            }],
+            adjacent: false,
+        }
+    }
+
+    fn top(&mut self) -> &mut Open<'a> {
+        let len = self.open.len();
+        #[allow(clippy::indexing_slicing)]
+        &mut self.open[len - 1]
+    }
+
+    /// Close the innermost group, putting its items where they belong
+    ///
+    /// A group closed by a paren ends where the paren does; otherwise it ends
+    /// with its last item.
+    fn close_top(&mut self, end: Option<Position>) {
+        if self.open.len() < 2 {
+            return;
+        }
+
+        let Some(closed) = self.open.pop() else {
+            return;
+        };
+
+        let (indent, line) = (self.indent.clone(), self.line);
+        let end = end.or_else(|| closed.items.last().map(|item| item.span.end));
+        let parent = self.top();
+        if closed.tail {
+            if let Some(last) = parent.items.last_mut() {
+                last.children.extend(closed.items);
+                if let Some(end) = end {
+                    last.extend_to(end);
+                }
+            }
+        } else if let Some(mut node) = collapse(closed.items) {
+            if closed.group == Group::Paren {
+                node.span.start = closed.start;
+                node.extend_to(end.unwrap_or(node.span.end));
+            }
+            parent.items.push(node);
+        } else if closed.group == Group::Paren {
+            let span = Span::new(closed.start, end.unwrap_or(closed.start));
+            parent.items.push(Node::nil(indent, line).with_span(span));
+        }
+    }
+
+    /// Close colon groups which already have something in them
+    fn end_siblings(&mut self) {
+        while self.top().group == Group::Colon && !self.top().items.is_empty() {
+            self.close_top(None);
+        }
+    }
+
+    /// Add a label
+    pub fn label(&mut self, name: Cow<'a, str>, span: Span) {
+        self.end_siblings();
+        let node = Node::new(name, self.indent.clone(), self.line).with_span(span);
+        self.top().items.push(node);
+        self.adjacent = true;
+    }
+
+    /// Open a paren or colon group
+    pub fn open(&mut self, group: Group, span: Span) {
+        let tail = self.adjacent;
+        if !tail {
+            self.end_siblings();
+        }
+
+        self.open.push(Open {
+            group,
+            tail,
+            start: span.start,
+            items: vec![],
+        });
+        self.adjacent = false;
+    }
+
+    /// Close the innermost paren group, and any colon groups within it
+    ///
+    /// Returns false if there's no paren open.
+    pub fn close(&mut self, span: Span) -> bool {
+        while self.top().group == Group::Colon {
+            self.close_top(None);
+        }
+
+        if self.top().group != Group::Paren {
+            return false;
+        }
+
+        self.close_top(Some(span.end));
+        self.adjacent = true;
+        true
+    }
+
+    /// Note significant whitespace, which separates siblings
+    pub fn space(&mut self) {
+        self.end_siblings();
+        self.adjacent = false;
+    }
+
+    /// Close everything left open and return the line's node, if it has one
+    pub fn finish(mut self) -> Option<Node<'a>> {
+        while self.open.len() > 1 {
+            self.close_top(None);
+        }
+
+        self.open.pop().and_then(|line| collapse(line.items))
+    }
+}
+
+/// Make a single node out of a list of items
+///
+/// A single item stands for itself. Otherwise the first item is the head and
+/// the rest its tail; a head which is more than a lone label stays an s-list.
+fn collapse(items: Vec<Node>) -> Option<Node> {
+    let mut items = items.into_iter();
+    let first = items.next()?;
+    let rest: Vec<Node> = items.collect();
+    let Some(end) = rest.last().map(|item| item.span.end) else {
+        return Some(first);
+    };
+
+    let (indent, line) = (first.indent.clone(), first.line);
+    let mut node = if first.is_label() {
+        Node {
+            children: Vec::new(),
+            ..first
+        }
+    } else {
+        Node::list(first, indent, line)
+    };
+
+    node.children = rest;
+    node.extend_to(end);
+    Some(node)
+}
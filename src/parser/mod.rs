@@ -0,0 +1,442 @@
+use document::Document;
+use error::Error;
+use lexer::{lex_located, Lexeme, Line, Open, Token};
+use position::{Position, Span};
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use self::arena::{Arena, NodeId};
+use self::line::{Group, LineBuilder};
+
+mod arena;
+mod line;
+#[cfg(test)]
+mod tests;
+
+/// A multiline label being read in
+#[derive(Clone, Debug, Default)]
+struct Multiline {
+    /// The indent of the line which opened the label
+    opener: String,
+
+    /// Anything after the quote on the line which opened the label
+    first: Option<String>,
+
+    /// The indent and text of each line so far
+    lines: Vec<(String, String)>,
+
+    /// Where the last of the label's text ends in the source
+    end: Position,
+}
+
+impl Multiline {
+    /// Read in a line if it's part of the label, or return false if it ends it
+    fn take(&mut self, line: &[Lexeme]) -> bool {
+        let indent = match line.first().map(|l| &l.token) {
+            Some(Token::Indent(i)) => i.0.as_ref(),
+            _ => "",
+        };
+
+        let tags: Vec<&Lexeme> = line
+            .iter()
+            .filter(|l| matches!(l.token, Token::Tag(_)))
+            .collect();
+        let text: Vec<&str> = tags
+            .iter()
+            .filter_map(|l| match &l.token {
+                Token::Tag(t) => Some(t.0.as_ref()),
+                _ => None,
+            })
+            .collect();
+
+        // Only a line with nothing but its indent is a blank line of the label,
+        // as the lexer has it; anything else less indented ends it
+        let blank = line.iter().all(|l| matches!(l.token, Token::Indent(_)));
+        let deeper = indent.len() > self.opener.len() && indent.starts_with(&self.opener);
+        if !(blank || deeper) {
+            return false;
+        }
+
+        if let Some(last) = tags.last() {
+            self.end = last.span.end;
+        }
+
+        self.lines.push((indent.into(), text.join(" ")));
+        true
+    }
+
+    /// The label as read so far: dedented lines, without trailing blank lines
+    fn label(&self) -> String {
+        let end = self
+            .lines
+            .iter()
+            .rposition(|(_, text)| !text.is_empty())
+            .map_or(0, |i| i + 1);
+
+        #[allow(clippy::indexing_slicing)]
+        let lines = &self.lines[..end];
+
+        let common = lines
+            .iter()
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(indent, _)| indent.as_str())
+            .fold(None, |common: Option<&str>, indent| {
+                Some(common.map_or(indent, |common| {
+                    let len = common
+                        .bytes()
+                        .zip(indent.bytes())
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    #[allow(clippy::indexing_slicing)]
+                    &common[..len]
+                }))
+            })
+            .unwrap_or("");
+
+        self.first
+            .iter()
+            .cloned()
+            .chain(lines.iter().map(|(indent, text)| {
+                if text.is_empty() {
+                    String::new()
+                } else {
+                    #[allow(clippy::indexing_slicing)]
+                    let extra = &indent[common.len()..];
+                    format!("{extra}{text}")
+                }
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Something the parser is doing, as reported to a hook set with [`Termpose::on_trace`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trace<'a> {
+    /// Stepping into the last node, for a line indented deeper
+    Dive,
+
+    /// Stepping back out, for a line indented shallower
+    Rise,
+
+    /// About to process the `index`th of the `length` tokens on a line
+    Token {
+        index: usize,
+        length: usize,
+        token: &'a Token<'a>,
+    },
+}
+
+type TraceHook = Arc<dyn Fn(Trace) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Termpose<'a> {
+    pub tokens: Vec<Line<'a>>,
+    pub positions: Vec<Position>,
+    arena: Arena<'a>,
+    node: NodeId,
+    current_line: usize,
+    indent_stack: Vec<Cow<'a, str>>,
+    trace: Option<TraceHook>,
+}
+
+impl Debug for Termpose<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Termpose")
+            .field("arena", &self.arena)
+            .field("node", &self.node)
+            .field("current_line", &self.current_line)
+            .field("indent_stack", &self.indent_stack)
+            .field("tracing", &self.trace.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Termpose<'_> {
+    fn default() -> Self {
+        Self {
+            tokens: vec![],
+            positions: vec![],
+            arena: Arena::default(),
+            node: Arena::root(),
+            current_line: 0,
+            indent_stack: vec![],
+            trace: None,
+        }
+    }
+}
+
+impl<'a> Termpose<'a> {
+    /// Create a new Termpose and start it off from a string
+    ///
+    /// # Errors
+    ///
+    /// If the string fails to lex.
+    pub fn new_from_str(input: &'a str) -> Result<Self, Error> {
+        let mut pose = Self::default();
+        pose.load_str(input)?;
+        Ok(pose)
+    }
+
+    /// Lex a string and load it in
+    ///
+    /// # Errors
+    ///
+    /// If the string fails to lex.
+    pub fn load_str(&mut self, input: &'a str) -> Result<(), Error> {
+        self.load_located(lex_located(input)?);
+        Ok(())
+    }
+
+    /// Load a list of lexed Lines
+    ///
+    /// Each line is taken to start where its first token does. Lines with no
+    /// tokens are assumed to follow on from the line before.
+    pub fn load(&mut self, toks: Vec<Line<'a>>) {
+        for tok in toks {
+            let position = if let Some(first) = tok.0.first() {
+                first.span.start
+            } else {
+                let line = self.positions.last().map_or(0, |pos| pos.line) + 1;
+                let offset = self
+                    .tokens
+                    .last()
+                    .and_then(|line| line.0.last())
+                    .map_or(0, |last| last.span.end.offset);
+                Position::new(line, 1, offset)
+            };
+
+            self.positions.push(position);
+            self.tokens.push(tok);
+        }
+    }
+
+    /// Load a list of lexed Lines along with where each starts in the source
+    pub fn load_located(&mut self, toks: Vec<(Position, Line<'a>)>) {
+        for (pos, tok) in toks {
+            self.positions.push(pos);
+            self.tokens.push(tok);
+        }
+    }
+
+    /// Call `hook` with everything the parser does from now on
+    ///
+    /// This is only meant for debugging the parser itself.
+    pub fn on_trace<F>(&mut self, hook: F)
+    where
+        F: Fn(Trace) + Send + Sync + 'static,
+    {
+        self.trace = Some(Arc::new(hook));
+    }
+
+    fn trace(&self, event: Trace) {
+        if let Some(hook) = &self.trace {
+            hook(event);
+        }
+    }
+
+    fn step_in(&mut self, position: Position) -> Result<(), Error> {
+        self.node = self
+            .arena
+            .last_child(self.node)
+            .ok_or(Error::UnexpectedIndent(position))?;
+        Ok(())
+    }
+
+    fn step_out(&mut self) -> bool {
+        match self.arena.parent(self.node) {
+            Some(n) => {
+                self.node = n;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn current_indent(&self) -> Cow<'a, str> {
+        self.indent_stack.last().cloned().unwrap_or_default()
+    }
+
+    /// How much deeper the current level is indented than the one it's in
+    fn indent_step(&self) -> Cow<'a, str> {
+        let outer = self
+            .indent_stack
+            .len()
+            .checked_sub(2)
+            .and_then(|i| self.indent_stack.get(i))
+            .map_or(0, |level| level.len());
+
+        match self.current_indent() {
+            Cow::Borrowed(indent) => Cow::Borrowed(indent.get(outer..).unwrap_or_default()),
+            Cow::Owned(indent) => Cow::Owned(indent.get(outer..).unwrap_or_default().into()),
+        }
+    }
+
+    /// Where the current line's content starts, past its indent
+    fn content_position(&self, line: &[Lexeme]) -> Position {
+        let mut pos = self
+            .positions
+            .get(self.current_line - 1)
+            .copied()
+            .unwrap_or_default();
+
+        if let Some(Token::Indent(i)) = line.first().map(|l| &l.token) {
+            pos.column += i.0.len();
+            pos.offset += i.0.len();
+        }
+
+        pos
+    }
+
+    /// Put together the document parsed so far
+    pub fn finalise(self) -> Document<'a> {
+        Document::new(self.arena.into_tree())
+    }
+
+    /// Move to the level of a line's indent
+    ///
+    /// A deeper indent must start with the current one, and steps into the last
+    /// node. A shallower indent must match one of the enclosing levels exactly,
+    /// and steps out as many levels as it drops.
+    fn indent(&mut self, indent: Cow<'a, str>, position: Position) -> Result<(), Error> {
+        let ci = self.current_indent();
+        if ci == indent {
+            return Ok(());
+        }
+
+        if indent.len() > ci.len() {
+            if !indent.starts_with(&*ci) {
+                return Err(Error::InconsistentIndent(position));
+            }
+
+            self.trace(Trace::Dive);
+            self.step_in(position)?;
+            self.indent_stack.push(indent);
+            return Ok(());
+        }
+
+        // Levels only get deeper along the stack, so this is how many to keep
+        let depth = self
+            .indent_stack
+            .iter()
+            .take_while(|level| level.len() <= indent.len())
+            .count();
+        let level = depth
+            .checked_sub(1)
+            .and_then(|i| self.indent_stack.get(i))
+            .map_or("", |level| level);
+
+        if level != indent {
+            return Err(if level.len() == indent.len() {
+                Error::InconsistentIndent(position)
+            } else {
+                Error::UnmatchedDedent(position)
+            });
+        }
+
+        while self.indent_stack.len() > depth {
+            self.trace(Trace::Rise);
+            self.indent_stack.pop();
+            self.step_out();
+        }
+
+        Ok(())
+    }
+
+    /// Read in the multiline label opened on `line`, from the lines after it
+    ///
+    /// The `rest` of the line after the quote, if anything, starts the label.
+    /// The lines of the label must all be loaded before the line opening it is
+    /// turned, as they're consumed along with it.
+    fn multiline(&mut self, line: &[Lexeme], quote: Span, rest: &[Lexeme]) -> (String, Span) {
+        let first = rest.iter().find(|l| match &l.token {
+            Token::Tag(t) => !t.0.trim().is_empty(),
+            _ => false,
+        });
+
+        let mut multiline = Multiline {
+            opener: match line.first().map(|l| &l.token) {
+                Some(Token::Indent(i)) => i.0.to_string(),
+                _ => String::new(),
+            },
+            first: first.and_then(|l| match &l.token {
+                Token::Tag(t) => Some(t.0.to_string()),
+                _ => None,
+            }),
+            lines: vec![],
+            end: first.map_or(quote.end, |l| l.span.end),
+        };
+
+        while let Some(next) = self.tokens.get(self.current_line) {
+            if !multiline.take(&next.0) {
+                break;
+            }
+            self.current_line += 1;
+        }
+
+        (multiline.label(), Span::new(quote.start, multiline.end))
+    }
+
+    /// Process one Line
+    ///
+    /// A line opening a multiline label is processed along with the label.
+    ///
+    /// # Errors
+    ///
+    /// If the line's structure doesn't fit with what came before.
+    pub fn turn(&mut self) -> Result<bool, Error> {
+        if self.current_line >= self.tokens.len() {
+            return Ok(false);
+        }
+
+        #[allow(clippy::indexing_slicing)]
+        let line = self.tokens[self.current_line].clone().0;
+        let length = line.len();
+        self.current_line += 1;
+        let position = self.content_position(&line);
+
+        // Lines with nothing but whitespace carry no structure
+        if !line.iter().any(|l| !matches!(l.token, Token::Indent(_))) {
+            return Ok(true);
+        }
+
+        if let Some(Token::Indent(s)) = line.first().map(|l| &l.token) {
+            self.indent(s.0.clone(), position)?;
+        }
+
+        let mut builder = LineBuilder::new(self.indent_step(), position.line);
+        for (i, Lexeme { token, span }) in line.iter().enumerate() {
+            self.trace(Trace::Token {
+                index: i,
+                length,
+                token,
+            });
+
+            match token {
+                Token::Indent(_) => {}
+                Token::Tag(t) => builder.label(t.0.clone(), *span),
+                Token::Open(Open::Quote) => {
+                    #[allow(clippy::indexing_slicing)]
+                    let (label, span) = self.multiline(&line, *span, &line[i + 1..]);
+                    builder.label(label.into(), span);
+                    break;
+                }
+                Token::Open(Open::Paren) => builder.open(Group::Paren, *span),
+                Token::Open(Open::Colon) => builder.open(Group::Colon, *span),
+                Token::Close(_) => {
+                    if !builder.close(*span) {
+                        return Err(Error::UnbalancedClose(span.start));
+                    }
+                }
+                Token::Sigspace => builder.space(),
+            }
+        }
+
+        if let Some(node) = builder.finish() {
+            self.arena.add(self.node, node);
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,645 @@
+use super::*;
+use node::{Head, Node};
+use Error;
+
+// Most tests only care about the shape of the tree, so these leave the spans out
+
+fn strip(mut node: Node<'_>) -> Node<'_> {
+    node.head_span = Span::default();
+    node.span = Span::default();
+    if let Some(Head::List(list)) = node.head {
+        node.head = Some(Head::List(Box::new(strip(*list))));
+    }
+    node.children = strip_all(node.children);
+    node
+}
+
+fn strip_all(nodes: Vec<Node<'_>>) -> Vec<Node<'_>> {
+    nodes.into_iter().map(strip).collect()
+}
+
+fn parse(input: &str) -> Result<Vec<Node<'_>>, Error> {
+    ::parse(input).map(strip_all)
+}
+
+fn node<'a>(name: &'a str, indent: &'a str, line: usize, children: Vec<Node<'a>>) -> Node<'a> {
+    let mut node = Node::new(name, indent, line);
+    node.children = children;
+    node
+}
+
+#[test]
+fn single_label() {
+    assert_eq!(parse("one\n"), Ok(vec![node("one", "", 1, vec![])]));
+}
+
+#[test]
+fn labels_on_a_line() {
+    assert_eq!(
+        parse("a lot of alots\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![
+                node("lot", "", 1, vec![]),
+                node("of", "", 1, vec![]),
+                node("alots", "", 1, vec![]),
+            ]
+        )])
+    );
+}
+
+#[test]
+fn siblings_after_indented_line() {
+    assert_eq!(
+        parse("\nroot\n    a lot\nallowed hallows\n    wand\n    cape\n"),
+        Ok(vec![
+            node(
+                "root",
+                "",
+                2,
+                vec![node("a", "    ", 3, vec![node("lot", "    ", 3, vec![])])]
+            ),
+            node(
+                "allowed",
+                "",
+                4,
+                vec![
+                    node("hallows", "", 4, vec![]),
+                    node("wand", "    ", 5, vec![]),
+                    node("cape", "    ", 6, vec![]),
+                ]
+            ),
+        ])
+    );
+}
+
+#[test]
+fn unclosed_parens_end_with_the_line() {
+    assert_eq!(
+        parse("a(b(c\nd\n"),
+        Ok(vec![
+            node(
+                "a",
+                "",
+                1,
+                vec![node("b", "", 1, vec![node("c", "", 1, vec![])])]
+            ),
+            node("d", "", 2, vec![]),
+        ])
+    );
+}
+
+#[test]
+fn whitespace_only_lines() {
+    assert_eq!(
+        parse("a\n    b\n        \nc\n"),
+        Ok(vec![
+            node("a", "", 1, vec![node("b", "    ", 2, vec![])]),
+            node("c", "", 4, vec![]),
+        ])
+    );
+}
+
+#[test]
+fn extra_close() {
+    assert_eq!(
+        parse("a\n  b)\n"),
+        Err(Error::UnbalancedClose(Position::new(2, 4, 5)))
+    );
+}
+
+#[test]
+fn close_with_no_paren_on_the_line() {
+    assert_eq!(
+        parse("a(\n  b)\n"),
+        Err(Error::UnbalancedClose(Position::new(2, 4, 6)))
+    );
+}
+
+#[test]
+fn same_depth_different_indent() {
+    assert_eq!(
+        parse("a\n  b\n\t\tc\n"),
+        Err(Error::InconsistentIndent(Position::new(3, 3, 8)))
+    );
+}
+
+#[test]
+fn missing_trailing_newline() {
+    assert_eq!(parse("a"), Ok(vec![node("a", "", 1, vec![])]));
+    assert_eq!(
+        parse("a\n  b"),
+        Ok(vec![node("a", "", 1, vec![node("b", "  ", 2, vec![])])])
+    );
+    assert_eq!(
+        parse("a \"\n  one\n  two"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![node("one\ntwo", "", 1, vec![])]
+        )])
+    );
+}
+
+#[test]
+fn line_numbers_count_blank_lines() {
+    assert_eq!(
+        parse("a\n\n\nb\n"),
+        Ok(vec![node("a", "", 1, vec![]), node("b", "", 4, vec![])])
+    );
+}
+
+#[test]
+fn turn_by_turn() {
+    let mut pose = Termpose::new_from_str("a\nb\n").unwrap();
+    assert_eq!(pose.turn(), Ok(true));
+    assert_eq!(
+        strip_all(pose.clone().finalise().children),
+        vec![node("a", "", 1, vec![])]
+    );
+    assert_eq!(pose.turn(), Ok(true));
+    assert_eq!(pose.turn(), Ok(false));
+    assert_eq!(
+        strip_all(pose.finalise().children),
+        vec![node("a", "", 1, vec![]), node("b", "", 2, vec![])]
+    );
+}
+
+#[test]
+fn multiline_mon_example() {
+    assert_eq!(
+        parse(
+            "
+mon
+   name \"courageous leafward\"
+   description \"
+      Plants healing bombs.
+      Standard attack.
+      Watch out, it's fragile!
+   stride 2
+"
+        ),
+        Ok(vec![node(
+            "mon",
+            "",
+            2,
+            vec![
+                node(
+                    "name",
+                    "   ",
+                    3,
+                    vec![node("courageous leafward", "   ", 3, vec![])]
+                ),
+                node(
+                    "description",
+                    "   ",
+                    4,
+                    vec![node(
+                        "Plants healing bombs.\nStandard attack.\nWatch out, it's fragile!",
+                        "   ",
+                        4,
+                        vec![]
+                    )]
+                ),
+                node("stride", "   ", 8, vec![node("2", "   ", 8, vec![])]),
+            ]
+        )])
+    );
+}
+
+#[test]
+fn multiline_keeps_relative_indent_and_inner_blank_lines() {
+    assert_eq!(
+        parse("a \"\n    one\n      two (2)\n\n    three\n\n\nb\n"),
+        Ok(vec![
+            node(
+                "a",
+                "",
+                1,
+                vec![node("one\n  two (2)\n\nthree", "", 1, vec![])]
+            ),
+            node("b", "", 8, vec![]),
+        ])
+    );
+}
+
+#[test]
+fn multiline_with_text_after_the_quote() {
+    assert_eq!(
+        parse("a \"one\n  two\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![node("one\ntwo", "", 1, vec![])]
+        )])
+    );
+    assert_eq!(
+        parse("a \"p\\tq\n    x\\ty\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![node("p\tq\nx\\ty", "", 1, vec![])]
+        )])
+    );
+    assert_eq!(
+        parse("a \"  \n    x\n"),
+        Ok(vec![node("a", "", 1, vec![node("x", "", 1, vec![])])])
+    );
+}
+
+#[test]
+fn multiline_as_line_head() {
+    assert_eq!(
+        parse("a\n  \"\n    one\n  b\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![node("one", "  ", 2, vec![]), node("b", "  ", 4, vec![]),]
+        )])
+    );
+}
+
+#[test]
+fn multiline_ends_at_a_line_with_no_labels() {
+    assert_eq!(
+        parse("desc \"\n    hello\n()\nnext\n"),
+        Ok(vec![
+            node("desc", "", 1, vec![node("hello", "", 1, vec![])]),
+            Node::nil("", 3),
+            node("next", "", 4, vec![]),
+        ])
+    );
+    assert_eq!(
+        parse("desc \"\n    hello\n\"\n    world\n"),
+        Ok(vec![
+            node("desc", "", 1, vec![node("hello", "", 1, vec![])]),
+            node("world", "", 3, vec![]),
+        ])
+    );
+}
+
+#[test]
+fn multiline_at_end_of_document() {
+    let mut pose = Termpose::new_from_str("a \"\n  one\n  two\n").unwrap();
+    while pose.turn().unwrap() {}
+    assert_eq!(
+        strip_all(pose.finalise().children),
+        vec![node("a", "", 1, vec![node("one\ntwo", "", 1, vec![])])]
+    );
+}
+
+fn leaf(name: &str) -> Node<'_> {
+    node(name, "", 1, vec![])
+}
+
+#[test]
+fn colon_takes_the_next_item() {
+    assert_eq!(
+        parse("x a:b c\n"),
+        Ok(vec![node(
+            "x",
+            "",
+            1,
+            vec![node("a", "", 1, vec![leaf("b")]), leaf("c")]
+        )])
+    );
+}
+
+#[test]
+fn colons_chain_to_the_right() {
+    assert_eq!(
+        parse("a:b:c:d\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![node("b", "", 1, vec![node("c", "", 1, vec![leaf("d")])])]
+        )])
+    );
+}
+
+#[test]
+fn colon_before_parens() {
+    assert_eq!(
+        parse("x a:b(c d) e\n"),
+        Ok(vec![node(
+            "x",
+            "",
+            1,
+            vec![
+                node(
+                    "a",
+                    "",
+                    1,
+                    vec![node("b", "", 1, vec![leaf("c"), leaf("d")])]
+                ),
+                leaf("e"),
+            ]
+        )])
+    );
+}
+
+#[test]
+fn colon_at_end_of_line() {
+    assert_eq!(parse("foo:\n"), Ok(vec![leaf("foo")]));
+}
+
+#[test]
+fn colon_closed_by_paren() {
+    assert_eq!(
+        parse("x (a:b) c\n"),
+        Ok(vec![node(
+            "x",
+            "",
+            1,
+            vec![node("a", "", 1, vec![leaf("b")]), leaf("c")]
+        )])
+    );
+}
+
+#[test]
+fn colons_within_parens() {
+    assert_eq!(
+        parse("strike drain:2 effect( damage:2 )\n"),
+        Ok(vec![node(
+            "strike",
+            "",
+            1,
+            vec![
+                node("drain", "", 1, vec![leaf("2")]),
+                node(
+                    "effect",
+                    "",
+                    1,
+                    vec![node("damage", "", 1, vec![leaf("2")])]
+                ),
+            ]
+        )])
+    );
+    assert_eq!(
+        parse("bomb drain:3 effect( heal:5 slow:1 )\n"),
+        Ok(vec![node(
+            "bomb",
+            "",
+            1,
+            vec![
+                node("drain", "", 1, vec![leaf("3")]),
+                node(
+                    "effect",
+                    "",
+                    1,
+                    vec![
+                        node("heal", "", 1, vec![leaf("5")]),
+                        node("slow", "", 1, vec![leaf("1")]),
+                    ]
+                ),
+            ]
+        )])
+    );
+}
+
+fn list<'a>(head: Node<'a>, children: Vec<Node<'a>>) -> Node<'a> {
+    let mut node = Node::list(head, String::new(), 1);
+    node.children = children;
+    node
+}
+
+#[test]
+fn list_as_head() {
+    let nodes = parse("(a b) c\n").unwrap();
+    assert_eq!(
+        nodes,
+        vec![list(node("a", "", 1, vec![leaf("b")]), vec![leaf("c")])]
+    );
+    assert_eq!(nodes[0].name(), None);
+    assert_eq!(nodes[0].head_list().and_then(Node::name), Some("a"));
+}
+
+#[test]
+fn nested_lists_as_heads() {
+    assert_eq!(
+        parse("((a b) c) d\n"),
+        Ok(vec![list(
+            list(node("a", "", 1, vec![leaf("b")]), vec![leaf("c")]),
+            vec![leaf("d")]
+        )])
+    );
+}
+
+#[test]
+fn list_head_takes_indented_tail() {
+    assert_eq!(
+        parse("(a b)\n  c\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![leaf("b"), node("c", "  ", 2, vec![])]
+        )])
+    );
+    assert_eq!(
+        parse("(a b) c\n  d\n"),
+        Ok(vec![list(
+            node("a", "", 1, vec![leaf("b")]),
+            vec![leaf("c"), node("d", "  ", 2, vec![])]
+        )])
+    );
+}
+
+#[test]
+fn empty_parens_are_nil() {
+    let nodes = parse("()\na ()\nb()\n").unwrap();
+    assert!(nodes[0].is_nil());
+    assert_eq!(nodes[1].children, vec![Node::nil(String::new(), 2)]);
+    assert_eq!(nodes[2], node("b", "", 3, vec![]));
+    assert!(!nodes[2].is_nil());
+}
+
+#[test]
+fn indented_first_line() {
+    assert_eq!(
+        parse("  a\n"),
+        Err(Error::UnexpectedIndent(Position::new(1, 3, 2)))
+    );
+}
+
+#[test]
+fn indent_under_a_line_without_nodes() {
+    assert_eq!(
+        parse(":\n  a\n"),
+        Err(Error::UnexpectedIndent(Position::new(2, 3, 4)))
+    );
+}
+
+#[test]
+fn trace_hook() {
+    use std::sync::Mutex;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut pose = Termpose::new_from_str("a\n  b\nc\n").unwrap();
+    let log = Arc::clone(&events);
+    pose.on_trace(move |event| {
+        log.lock().unwrap().push(match event {
+            Trace::Dive => "dive".to_string(),
+            Trace::Rise => "rise".to_string(),
+            Trace::Token { index, length, .. } => format!("{index}/{length}"),
+        });
+    });
+    while pose.turn().unwrap() {}
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["0/2", "1/2", "dive", "0/2", "1/2", "rise", "0/2", "1/2"]
+    );
+}
+
+#[test]
+fn multi_level_dedent() {
+    assert_eq!(
+        parse("a\n  b\n    c\n      d\n  e\nf\n"),
+        Ok(vec![
+            node(
+                "a",
+                "",
+                1,
+                vec![
+                    node(
+                        "b",
+                        "  ",
+                        2,
+                        vec![node("c", "  ", 3, vec![node("d", "  ", 4, vec![])])]
+                    ),
+                    node("e", "  ", 5, vec![]),
+                ]
+            ),
+            node("f", "", 6, vec![]),
+        ])
+    );
+}
+
+#[test]
+fn mixed_indent_steps() {
+    assert_eq!(
+        parse("a\n\tb\n\t  c\n\td\n"),
+        Ok(vec![node(
+            "a",
+            "",
+            1,
+            vec![
+                node("b", "\t", 2, vec![node("c", "  ", 3, vec![])]),
+                node("d", "\t", 4, vec![]),
+            ]
+        )])
+    );
+}
+
+#[test]
+fn deeper_indent_with_different_whitespace() {
+    assert_eq!(
+        parse("a\n\tb\n    c\n"),
+        Err(Error::InconsistentIndent(Position::new(3, 5, 9)))
+    );
+}
+
+#[test]
+fn dedent_with_different_whitespace() {
+    assert_eq!(
+        parse("a\n  b\n    c\n\t d\n"),
+        Err(Error::InconsistentIndent(Position::new(4, 3, 14)))
+    );
+}
+
+#[test]
+fn dedent_between_levels() {
+    assert_eq!(
+        parse("a\n    b\n  c\n"),
+        Err(Error::UnmatchedDedent(Position::new(3, 3, 10)))
+    );
+    assert_eq!(
+        parse("a\n  b\n      c\n    d\n"),
+        Err(Error::UnmatchedDedent(Position::new(4, 5, 18)))
+    );
+}
+
+#[test]
+fn nodes_borrow_from_the_source() {
+    let source = String::from("a b\n  \"c\"\n");
+    let nodes = parse(&source).unwrap();
+    assert!(matches!(
+        nodes[0].head,
+        Some(Head::Label(Cow::Borrowed("a")))
+    ));
+    assert!(matches!(nodes[0].children[1].indent, Cow::Borrowed("  ")));
+
+    let owned: Node<'static> = nodes[0].clone().into_owned();
+    drop(nodes);
+    drop(source);
+    assert_eq!(owned.children[1].name(), Some("c"));
+}
+
+fn text(source: &str, span: Span) -> &str {
+    &source[span.range()]
+}
+
+#[test]
+fn node_spans() {
+    let source = "a (b c) d:e\n  f g\nh\n";
+    let doc = ::parse_document(source).unwrap();
+    let a = &doc.children[0];
+
+    assert_eq!(text(source, doc.span), source.trim_end());
+    assert_eq!(text(source, a.head_span), "a");
+    assert_eq!(text(source, a.span), "a (b c) d:e\n  f g");
+    assert_eq!(text(source, a.children[0].head_span), "b");
+    assert_eq!(text(source, a.children[0].span), "(b c)");
+    assert_eq!(text(source, a.children[1].span), "d:e");
+    assert_eq!(text(source, a.children[2].span), "f g");
+    assert_eq!(text(source, doc.children[1].span), "h");
+    assert_eq!(
+        a.children[2].span,
+        Span::new(Position::new(2, 3, 14), Position::new(2, 6, 17))
+    );
+}
+
+#[test]
+fn list_head_and_nil_spans() {
+    let source = "(a b) c\n()\n";
+    let doc = ::parse_document(source).unwrap();
+
+    assert_eq!(text(source, doc.children[0].head_span), "(a b)");
+    assert_eq!(text(source, doc.children[0].span), "(a b) c");
+    assert_eq!(text(source, doc.children[1].span), "()");
+}
+
+#[test]
+fn multiline_spans() {
+    let source = "x \"\n  one\n  two\n\ny\n";
+    let doc = ::parse_document(source).unwrap();
+    let x = &doc.children[0];
+
+    assert_eq!(text(source, x.children[0].span), "\"\n  one\n  two");
+    assert_eq!(text(source, x.span), "x \"\n  one\n  two");
+}
+
+#[test]
+fn loaded_lines_keep_their_spans() {
+    let source = "a (b c)\n  d\n\ne \"\n  f\n";
+    let mut loaded = Termpose::default();
+    loaded.load(::lexer::lex(source).unwrap());
+    while loaded.turn().unwrap() {}
+
+    let mut located = Termpose::default();
+    located.load_located(lex_located(source).unwrap());
+    while located.turn().unwrap() {}
+
+    assert_eq!(loaded.positions, located.positions);
+    let doc = loaded.finalise();
+    assert_eq!(text(source, doc.children[0].span), "a (b c)\n  d");
+    assert_eq!(text(source, doc.children[1].span), "e \"\n  f");
+}
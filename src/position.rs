@@ -0,0 +1,112 @@
+use std::fmt;
+use std::ops::Range;
+
+/// A location in the source text
+///
+/// Lines and columns are 1-based, and columns count characters, not bytes. The
+/// offset is the 0-based byte index into the source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize, offset: usize) -> Self {
+        Self {
+            line,
+            column,
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// A stretch of the source text, from `start` up to but not including `end`
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// The byte range of the span in the source
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start.offset == self.end.offset
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start.line == self.end.line {
+            write!(
+                f,
+                "line {}, columns {}-{}",
+                self.start.line, self.start.column, self.end.column
+            )
+        } else {
+            write!(f, "{} to {}", self.start, self.end)
+        }
+    }
+}
+
+/// Turns byte offsets into a source into full Positions
+///
+/// A line ends at a `\n`, a `\r\n`, or a lone `\r`.
+pub(crate) struct Locator<'src> {
+    source: &'src str,
+    line_starts: Vec<usize>,
+}
+
+impl<'src> Locator<'src> {
+    pub fn new(source: &'src str) -> Self {
+        let bytes = source.as_bytes();
+        let mut line_starts = vec![0];
+        for (i, b) in bytes.iter().enumerate() {
+            match b {
+                b'\n' => line_starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => line_starts.push(i + 1),
+                _ => {}
+            }
+        }
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn locate(&self, offset: usize) -> Position {
+        let offset = offset.min(self.source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+
+        let start = self.line_starts.get(line).copied().unwrap_or(0);
+        let column = self
+            .source
+            .get(start..offset)
+            .map_or(offset - start, |s| s.chars().count());
+
+        Position::new(line + 1, column + 1, offset)
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.locate(start), self.locate(end))
+    }
+}
@@ -0,0 +1,364 @@
+use document::Document;
+use node::{Head, Node};
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+#[cfg(test)]
+mod tests;
+
+/// Characters which can't appear unescaped in a bare label
+const SPECIAL: &str = " \t\r\n\"\\:()";
+
+/// How much of a tree goes on each line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Every node on its own line, with its children indented below it
+    Indented,
+
+    /// Every top-level node on a single line, however long
+    Inline,
+
+    /// Nodes on a single line where they fit within the width, and indented
+    /// like [`Layout::Indented`] where they don't
+    Hybrid,
+
+    /// Like [`Layout::Hybrid`], but only nodes with nothing but labels in
+    /// their tails go on a single line: `key value` pairs, and lists of values
+    Shallow,
+}
+
+/// How to write a node's tail within a line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nesting {
+    /// Always in parens: `a(b)`, `a(b c)`
+    Parens,
+
+    /// With a colon where there's a single child: `a:b`, `a(b c)`
+    Colons,
+}
+
+/// How to write trees out as termpose
+///
+/// The default is the canonical style: fully indented with four spaces, and
+/// parens wherever structure has to go within a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub layout: Layout,
+
+    /// The longest a line may be, in characters, for [`Layout::Hybrid`] and
+    /// [`Layout::Shallow`]
+    pub width: usize,
+
+    /// What each level of children is indented by
+    pub indent: String,
+
+    pub nesting: Nesting,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Indented,
+            width: 80,
+            indent: "    ".into(),
+            nesting: Nesting::Parens,
+        }
+    }
+}
+
+impl Style {
+    /// The canonical style
+    pub fn indented() -> Self {
+        Self::default()
+    }
+
+    /// Each top-level node on a single line
+    pub fn inline() -> Self {
+        Self {
+            layout: Layout::Inline,
+            ..Self::default()
+        }
+    }
+
+    /// Nodes on a single line where they fit in `width` characters
+    pub fn hybrid(width: usize) -> Self {
+        Self {
+            layout: Layout::Hybrid,
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Nodes with only labels in their tails on a single line where they fit
+    /// in `width` characters
+    pub fn shallow(width: usize) -> Self {
+        Self {
+            layout: Layout::Shallow,
+            width,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_indent<I: Into<String>>(mut self, indent: I) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_nesting(mut self, nesting: Nesting) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    /// Write nodes out in this style
+    ///
+    /// Whatever the layout, some structure can only be written within a line:
+    /// s-list heads, and the first child of a node headed by one.
+    ///
+    /// Labels are written bare where possible, escaped where they only need a
+    /// few escapes, and quoted otherwise. Labels spanning several lines are
+    /// written as multiline labels when they stand on a line of their own and
+    /// would read back the same; otherwise their line breaks are escaped.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `out` fails.
+    pub fn write<W: Write>(&self, out: &mut W, nodes: &[Node]) -> fmt::Result {
+        nodes
+            .iter()
+            .try_for_each(|node| self.write_node(out, node, 0))
+    }
+
+    /// Write nodes out in this style, to a string
+    pub fn print(&self, nodes: &[Node]) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail
+        let _ = self.write(&mut out, nodes);
+        out
+    }
+
+    /// Write a node and its children, starting on a line `depth` levels deep
+    fn write_node<W: Write>(&self, out: &mut W, node: &Node, depth: usize) -> fmt::Result {
+        let indent = self.indent.repeat(depth);
+        out.write_str(&indent)?;
+
+        if let Some(line) = self.whole_line(node, indent.chars().count()) {
+            return writeln!(out, "{line}");
+        }
+
+        let rest = match &node.head {
+            Some(Head::Label(name)) if node.children.is_empty() => {
+                return match multiline(name) {
+                    Some(lines) => self.write_multiline(out, &lines, &indent),
+                    None => writeln!(out, "{}", label(name)),
+                };
+            }
+            Some(Head::Label(name)) => {
+                out.write_str(&label(name))?;
+                &node.children[..]
+            }
+            head => {
+                let mut line = String::new();
+                self.head(&mut line, head.as_ref());
+                let mut children = node.children.iter();
+                if let Some(first) = children.next() {
+                    line.push(' ');
+                    self.item(&mut line, first);
+                }
+
+                out.write_str(&line)?;
+                children.as_slice()
+            }
+        };
+
+        out.write_char('\n')?;
+        rest.iter()
+            .try_for_each(|child| self.write_node(out, child, depth + 1))
+    }
+
+    /// The whole of a node as a single line, if the layout puts it on one
+    fn whole_line(&self, node: &Node, indent: usize) -> Option<String> {
+        let width = match self.layout {
+            Layout::Indented => return None,
+            Layout::Inline => usize::MAX,
+            Layout::Hybrid if spans_lines(node) => return None,
+            Layout::Shallow if spans_lines(node) || !node.children.iter().all(Node::is_label) => {
+                return None
+            }
+            Layout::Hybrid | Layout::Shallow => self.width.saturating_sub(indent),
+        };
+
+        let mut line = String::new();
+        self.line(&mut line, node);
+        if line.chars().count() <= width {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    /// Write the whole of a node within a single line, however long
+    pub(crate) fn line(&self, out: &mut String, node: &Node) {
+        self.head(out, node.head.as_ref());
+        for child in &node.children {
+            out.push(' ');
+            self.item(out, child);
+        }
+    }
+
+    /// Write a head within a line
+    fn head(&self, out: &mut String, head: Option<&Head>) {
+        match head {
+            Some(Head::Label(name)) => out.push_str(&label(name)),
+            Some(Head::List(list)) => self.item(out, list),
+            None => out.push_str("()"),
+        }
+    }
+
+    /// Write a node within a line, as a single item
+    ///
+    /// A tail follows a label head directly, in parens or after a colon, but a
+    /// node headed by anything else has to be wrapped in parens as a whole.
+    pub(crate) fn item(&self, out: &mut String, node: &Node) {
+        match (&node.head, &node.children[..]) {
+            (Some(Head::Label(name)), []) => out.push_str(&label(name)),
+            (Some(Head::Label(name)), [child]) if self.nesting == Nesting::Colons => {
+                out.push_str(&label(name));
+                out.push(':');
+                self.item(out, child);
+            }
+            (Some(Head::Label(name)), children) => {
+                out.push_str(&label(name));
+                out.push('(');
+                self.items(out, children);
+                out.push(')');
+            }
+            (None, []) => out.push_str("()"),
+            (Some(Head::List(list)), []) => self.item(out, list),
+            (head, children) => {
+                out.push('(');
+                self.head(out, head.as_ref());
+                out.push(' ');
+                self.items(out, children);
+                out.push(')');
+            }
+        }
+    }
+
+    /// Write nodes within a line, separated by spaces
+    fn items(&self, out: &mut String, nodes: &[Node]) {
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            self.item(out, node);
+        }
+    }
+
+    /// Write the opening quote and lines of a multiline label
+    fn write_multiline<W: Write>(&self, out: &mut W, lines: &[&str], indent: &str) -> fmt::Result {
+        out.write_str("\"\n")?;
+        for line in lines {
+            if line.is_empty() {
+                out.write_char('\n')?;
+            } else {
+                writeln!(out, "{indent}{}{line}", self.indent)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Write nodes out as canonical termpose
+///
+/// See [`Style`] for other ways to write them.
+///
+/// # Errors
+///
+/// If writing to `out` fails.
+pub fn write<W: Write>(out: &mut W, nodes: &[Node]) -> fmt::Result {
+    Style::default().write(out, nodes)
+}
+
+/// Whether any label in a node has a line break in it
+fn spans_lines(node: &Node) -> bool {
+    let head = match &node.head {
+        Some(Head::Label(name)) => name.contains(['\n', '\r']),
+        Some(Head::List(list)) => spans_lines(list),
+        None => false,
+    };
+
+    head || node.children.iter().any(spans_lines)
+}
+
+/// The lines of a label, if it can be written as a multiline label
+///
+/// Multiline labels drop trailing blank lines, blank out lines with nothing but
+/// whitespace, and dedent their lines, so labels that would lose something to
+/// that are left on one line.
+fn multiline(name: &str) -> Option<Vec<&str>> {
+    if !name.contains('\n') || name.contains('\r') {
+        return None;
+    }
+
+    let lines: Vec<&str> = name.split('\n').collect();
+    let blank = |line: &&str| line.chars().all(|c| c == ' ' || c == '\t');
+    let ragged = |line: &&str| !line.is_empty() && blank(line);
+    let unindented = |line: &&str| !line.starts_with([' ', '\t']);
+
+    let keeps = !lines.last().is_none_or(blank)
+        && !lines.iter().any(ragged)
+        && lines.iter().filter(|line| !line.is_empty()).any(unindented);
+    if keeps {
+        Some(lines)
+    } else {
+        None
+    }
+}
+
+/// Write a label within a line: bare, escaped, or quoted
+///
+/// Labels with spaces in them are quoted, and any others which can't be bare
+/// are escaped, per SPEC §3.
+pub(crate) fn label(name: &str) -> Cow<'_, str> {
+    if !name.is_empty() && !name.contains(|c| SPECIAL.contains(c)) {
+        Cow::Borrowed(name)
+    } else if name.is_empty() || name.contains(' ') {
+        Cow::Owned(format!("\"{}\"", escape(name, "\\\"")))
+    } else {
+        Cow::Owned(escape(name, SPECIAL))
+    }
+}
+
+/// Escape the backslash and any of `special`, as well as line breaks and tabs
+fn escape(name: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(name.len() + 2);
+    for c in name.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c == '\\' || special.contains(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Style::default().write_node(f, self, 0)
+    }
+}
+
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write(f, &self.children)
+    }
+}
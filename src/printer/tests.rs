@@ -0,0 +1,281 @@
+use super::*;
+use node::Node;
+use parse;
+
+// Round trips compare the shape of trees, as lines, indents and spans change
+
+fn sexp(node: &Node) -> String {
+    let head = match &node.head {
+        Some(Head::Label(name)) => format!("{name:?}"),
+        Some(Head::List(list)) => sexp(list),
+        None => String::new(),
+    };
+
+    let tail: Vec<String> = node.children.iter().map(sexp).collect();
+    if tail.is_empty() && node.head.is_some() {
+        head
+    } else {
+        format!(
+            "({})",
+            Some(head)
+                .into_iter()
+                .chain(tail)
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    }
+}
+
+fn shape(input: &str) -> Vec<String> {
+    parse(input).unwrap().iter().map(sexp).collect()
+}
+
+fn print(input: &str) -> String {
+    let mut out = String::new();
+    write(&mut out, &parse(input).unwrap()).unwrap();
+    out
+}
+
+fn round_trip(input: &str) {
+    let printed = print(input);
+    assert_eq!(shape(&printed), shape(input), "printed as:\n{printed}");
+    assert_eq!(print(&printed), printed, "not stable");
+}
+
+#[test]
+fn labels_each_on_their_own_line() {
+    assert_eq!(
+        print("root\n    a lot of alots\nallowed hallows\n"),
+        "root\n    a\n        lot\n        of\n        alots\nallowed\n    hallows\n"
+    );
+}
+
+#[test]
+fn label_forms() {
+    assert_eq!(label("elephants"), "elephants");
+    assert_eq!(label("les_éléphants"), "les_éléphants");
+    assert_eq!(label("~!@#$%^&*"), "~!@#$%^&*");
+    assert_eq!(label("all\\is\\ashes"), "all\\\\is\\\\ashes");
+    assert_eq!(label("\"magical\""), "\\\"magical\\\"");
+    assert_eq!(label("a:b"), "a\\:b");
+    assert_eq!(label("life\nunlife"), "life\\nunlife");
+    assert_eq!(label("data\tforever"), "data\\tforever");
+    assert_eq!(label("home sweet home"), "\"home sweet home\"");
+    assert_eq!(label("Incredibles (2)"), "\"Incredibles (2)\"");
+    assert_eq!(label("say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+    assert_eq!(label(""), "\"\"");
+}
+
+#[test]
+fn list_heads_and_nil() {
+    assert_eq!(print("(a b) c d\n"), "a(b) c\n    d\n");
+    assert_eq!(print("a ()\n"), "a\n    ()\n");
+    assert_eq!(print("((a b) c) d\n"), "(a(b) c) d\n");
+}
+
+#[test]
+fn multiline_labels() {
+    assert_eq!(
+        print("description \"\n   Plants healing bombs.\n\n     Standard attack.\n"),
+        "description\n    \"\n        Plants healing bombs.\n\n          Standard attack.\n"
+    );
+}
+
+#[test]
+fn multiline_only_where_it_reads_back_the_same() {
+    assert_eq!(multiline("one line"), None);
+    assert_eq!(multiline("a\nb"), Some(vec!["a", "b"]));
+    assert_eq!(multiline("\na\n\nb"), Some(vec!["", "a", "", "b"]));
+    assert_eq!(multiline("a\nb\n"), None);
+    assert_eq!(multiline("  a\n  b"), None);
+    assert_eq!(multiline("a\n  \nb"), None);
+    assert_eq!(multiline("a\r\nb"), None);
+}
+
+#[test]
+fn multiline_heads_stay_on_one_line() {
+    let mut node = Node::new("a\nb", "", 1);
+    node.children.push(Node::new("c", "", 1));
+    assert_eq!(node.to_string(), "a\\nb\n    c\n");
+}
+
+#[test]
+fn round_trips() {
+    round_trip("one\n");
+    round_trip("a lot of alots\n");
+    round_trip("a:b c:d:e\n");
+    round_trip("a(b c) d(e(f) g)\n");
+    round_trip("(a b) c\n    d\n    (e f) g\n");
+    round_trip("a () (()) (() b)\n");
+    round_trip("\"x y\" \"\" \\:\\(\\) \"tab\\there\"\n");
+    round_trip("mon\n   name \"courageous leafward\"\n   description \"\n      Plants healing bombs.\n      Standard attack.\n   stride 2\n");
+    round_trip("a \"first\n   second\n");
+    round_trip("a \"\n      deeper\n   shallower\n");
+}
+
+/// Check that printed nodes parse back into the same shape
+fn reads_back(nodes: &[Node]) {
+    let mut printed = String::new();
+    write(&mut printed, nodes).unwrap();
+    assert_eq!(
+        shape(&printed),
+        nodes.iter().map(sexp).collect::<Vec<_>>(),
+        "printed as:\n{printed}"
+    );
+}
+
+fn keyed<'a>(key: &'a str, value: &'a str) -> Node<'a> {
+    let mut node = Node::new(key, "", 1);
+    node.children.push(Node::new(value, "", 1));
+    node
+}
+
+#[test]
+fn multiline_labels_before_other_lines() {
+    reads_back(&[keyed("a", "one\ntwo"), Node::nil("", 2)]);
+    reads_back(&[keyed("a", "one\ntwo"), Node::new("three\nfour", "", 2)]);
+    reads_back(&[Node::new("\na\u{2603}\r", "", 1), Node::nil("", 2)]);
+
+    let mut x = Node::new("x", "", 1);
+    x.children = vec![
+        keyed("a", "one\ntwo"),
+        Node::nil("", 2),
+        Node::new("three\nfour", "", 3),
+    ];
+    reads_back(&[x]);
+}
+
+#[test]
+fn displays_documents() {
+    let doc = ::parse_document("a b\nc\n").unwrap();
+    assert_eq!(doc.to_string(), "a\n    b\nc\n");
+}
+
+const MON: &str = "mon
+   name \"courageous leafward\"
+   description \"
+      Plants healing bombs.
+      Standard attack.
+   stride 2
+   abilities
+      move
+      strike drain:2 effect( damage:2 )
+      bomb drain:3 effect( heal:5 slow:1 )
+";
+
+fn styles() -> Vec<Style> {
+    vec![
+        Style::indented(),
+        Style::inline(),
+        Style::inline().with_nesting(Nesting::Colons),
+        Style::hybrid(40),
+        Style::hybrid(40).with_nesting(Nesting::Colons),
+        Style::hybrid(0).with_indent("\t"),
+        Style::shallow(40),
+        Style::shallow(0).with_nesting(Nesting::Colons),
+    ]
+}
+
+#[test]
+fn inline_style() {
+    assert_eq!(
+        Style::inline().print(&parse("a\n    b c\n    d:e\nf\n").unwrap()),
+        "a b(c) d(e)\nf\n"
+    );
+    assert_eq!(
+        Style::inline()
+            .with_nesting(Nesting::Colons)
+            .print(&parse("a\n    b c\n    d:e:f\n    g(h i)\n").unwrap()),
+        "a b:c d:e:f g(h i)\n"
+    );
+}
+
+#[test]
+fn hybrid_style() {
+    let style = Style::hybrid(44).with_nesting(Nesting::Colons);
+    assert_eq!(
+        style.print(&parse(MON).unwrap()),
+        "mon
+    name \"courageous leafward\"
+    description
+        \"
+            Plants healing bombs.
+            Standard attack.
+    stride 2
+    abilities
+        move
+        strike drain:2 effect:damage:2
+        bomb drain:3 effect(heal:5 slow:1)
+"
+    );
+}
+
+#[test]
+fn shallow_style() {
+    assert_eq!(
+        Style::shallow(44).print(&parse(MON).unwrap()),
+        "mon
+    name \"courageous leafward\"
+    description
+        \"
+            Plants healing bombs.
+            Standard attack.
+    stride 2
+    abilities
+        move
+        strike
+            drain 2
+            effect
+                damage 2
+        bomb
+            drain 3
+            effect
+                heal 5
+                slow 1
+"
+    );
+}
+
+#[test]
+fn hybrid_counts_the_indent_against_the_width() {
+    let nodes = parse("abcdefgh\n    b c\n").unwrap();
+    assert_eq!(Style::hybrid(13).print(&nodes), "abcdefgh b(c)\n");
+    assert_eq!(Style::hybrid(12).print(&nodes), "abcdefgh\n    b c\n");
+    assert_eq!(
+        Style::hybrid(6).print(&nodes),
+        "abcdefgh\n    b\n        c\n"
+    );
+}
+
+#[test]
+fn indent_string() {
+    let nodes = parse("a\n    b c\n").unwrap();
+    assert_eq!(
+        Style::indented().with_indent("\t").print(&nodes),
+        "a\n\tb\n\t\tc\n"
+    );
+}
+
+#[test]
+fn round_trips_in_every_style() {
+    let inputs = [
+        MON,
+        "a:b c:d:e\n",
+        "a(b c) d(e(f) g)\n",
+        "(a b) c\n    d\n    (e f) g\n",
+        "a () (()) (() b) c:()\n",
+        "\"x y\" \"\" \\:\\(\\) \"tab\\there\"\n",
+    ];
+
+    for style in styles() {
+        for input in &inputs {
+            let printed = style.print(&parse(input).unwrap());
+            assert_eq!(
+                shape(&printed),
+                shape(input),
+                "{style:?} printed:\n{printed}"
+            );
+            assert_eq!(style.print(&parse(&printed).unwrap()), printed, "{style:?}");
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Finding nodes by path
+//!
+//! A query is a `/`-separated path of steps, each matched against the tail of
+//! the nodes matched by the step before it, starting from the nodes queried:
+//!
+//!  - a label matches s-lists headed by that label, and can be quoted, as
+//!    `"a/b"`, when it has `/`, `*`, `[`, `]` or `"` in it;
+//!  - `*` matches any s-list;
+//!  - `**` matches any number of levels of tails, including none, so
+//!    `**/drain` finds `drain` anywhere, and `abilities/**` everything under
+//!    `abilities`;
+//!  - `[n]` after a label or `*` keeps only the nth of its matches under each
+//!    s-list, counting from 0.
+//!
+//! Queries only look through tails, never into s-lists used as heads.
+
+use node::Node;
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::ptr;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+/// A parsed query
+///
+/// Queries can be parsed once with [`str::parse`] and run many times with
+/// [`Node::query`] or [`Query::find`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    /// `**`
+    Descend,
+    Match {
+        /// The label to match, or None for `*`
+        label: Option<String>,
+        index: Option<usize>,
+    },
+}
+
+/// A query which can't be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    /// Where the problem is, counting characters from 1
+    pub column: usize,
+
+    /// What's wrong there
+    pub problem: &'static str,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.problem, self.column)
+    }
+}
+
+impl StdError for QueryError {}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(path: &str) -> Result<Self, QueryError> {
+        let mut chars = path.chars().enumerate().peekable();
+        let mut steps = vec![];
+        loop {
+            let column = chars.peek().map_or(path.chars().count(), |&(i, _)| i) + 1;
+            let error = |problem| Err(QueryError { column, problem });
+
+            let step = match chars.peek() {
+                None | Some((_, '/')) => return error("expected a step"),
+                Some((_, '*')) => {
+                    chars.next();
+                    if chars.next_if(|&(_, c)| c == '*').is_some() {
+                        Step::Descend
+                    } else {
+                        Step::Match {
+                            label: None,
+                            index: None,
+                        }
+                    }
+                }
+                Some((_, '"')) => {
+                    chars.next();
+                    let mut label = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, '\\')) => match chars.next() {
+                                Some((_, c)) => label.push(c),
+                                None => return error("unterminated quote"),
+                            },
+                            Some((_, c)) => label.push(c),
+                            None => return error("unterminated quote"),
+                        }
+                    }
+                    Step::Match {
+                        label: Some(label),
+                        index: None,
+                    }
+                }
+                Some(_) => {
+                    let mut label = String::new();
+                    while let Some((_, c)) = chars.next_if(|&(_, c)| !"/*[]\"".contains(c)) {
+                        label.push(c);
+                    }
+                    if label.is_empty() {
+                        return error("unexpected character");
+                    }
+                    Step::Match {
+                        label: Some(label),
+                        index: None,
+                    }
+                }
+            };
+
+            let step = match (step, chars.peek()) {
+                (Step::Match { label, .. }, Some(&(i, '['))) => {
+                    chars.next();
+                    let mut digits = String::new();
+                    while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_ascii_digit()) {
+                        digits.push(c);
+                    }
+                    let Some(_) = chars.next_if(|&(_, c)| c == ']') else {
+                        return Err(QueryError {
+                            column: i + digits.len() + 2,
+                            problem: "expected a number and `]`",
+                        });
+                    };
+                    let Ok(index) = digits.parse() else {
+                        return Err(QueryError {
+                            column: i + 2,
+                            problem: "expected a number and `]`",
+                        });
+                    };
+                    Step::Match {
+                        label,
+                        index: Some(index),
+                    }
+                }
+                (step, _) => step,
+            };
+            steps.push(step);
+
+            match chars.next() {
+                None => return Ok(Self { steps }),
+                Some((_, '/')) => {}
+                Some((i, _)) => {
+                    return Err(QueryError {
+                        column: i + 1,
+                        problem: "expected `/` between steps",
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl Query {
+    /// Run the query over a list of nodes, such as a whole document
+    ///
+    /// Matches are in the order they appear in the source.
+    pub fn find<'n, 'a>(&self, nodes: &'n [Node<'a>]) -> Vec<&'n Node<'a>> {
+        let mut tails = vec![nodes];
+        let mut found = vec![];
+        for step in &self.steps {
+            match step {
+                Step::Descend => {
+                    let mut seen = HashSet::new();
+                    let mut all = vec![];
+                    for tail in tails {
+                        descend(tail, &mut seen, &mut all);
+                    }
+                    tails = all;
+                    found = tails.iter().flat_map(|tail| tail.iter()).collect();
+                }
+                Step::Match { label, index } => {
+                    found = tails
+                        .iter()
+                        .flat_map(|tail| {
+                            let matches = tail.iter().filter(move |node| match label {
+                                Some(label) => node.name() == Some(label),
+                                None => true,
+                            });
+                            let (skip, take) = index.map_or((0, usize::MAX), |index| (index, 1));
+                            matches.skip(skip).take(take)
+                        })
+                        .collect();
+                    tails = found.iter().map(|node| &node.children[..]).collect();
+                }
+            }
+        }
+
+        // `**` can match deeper nodes before shallower ones which come later
+        if self.steps.contains(&Step::Descend) {
+            let mut order = HashMap::new();
+            number(nodes, &mut order);
+            found.sort_by_key(|node| order[&ptr::from_ref(*node)]);
+        }
+
+        found
+    }
+}
+
+/// Number every node in the order they appear in the source
+fn number<'a>(tail: &[Node<'a>], order: &mut HashMap<*const Node<'a>, usize>) {
+    for node in tail {
+        let next = order.len();
+        order.insert(node, next);
+        number(&node.children, order);
+    }
+}
+
+/// Collect a tail and every tail under it, unless already seen
+fn descend<'n, 'a>(
+    tail: &'n [Node<'a>],
+    seen: &mut HashSet<*const Node<'a>>,
+    all: &mut Vec<&'n [Node<'a>]>,
+) {
+    if tail.is_empty() || !seen.insert(tail.as_ptr()) {
+        return;
+    }
+
+    all.push(tail);
+    for node in tail {
+        descend(&node.children, seen, all);
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Find the nodes in this node's tail which match a [`Query`]
+    pub fn query(&self, query: &Query) -> Vec<&Node<'a>> {
+        query.find(&self.children)
+    }
+
+    /// Find the first node in this node's tail which matches a path
+    ///
+    /// A path which isn't a valid [query](crate::Query) matches nothing.
+    pub fn get(&self, path: &str) -> Option<&Node<'a>> {
+        self.get_all(path).into_iter().next()
+    }
+
+    /// Find all the nodes in this node's tail which match a path
+    ///
+    /// A path which isn't a valid [query](crate::Query) matches nothing.
+    pub fn get_all(&self, path: &str) -> Vec<&Node<'a>> {
+        path.parse()
+            .map(|query| self.query(&query))
+            .unwrap_or_default()
+    }
+}
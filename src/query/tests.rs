@@ -0,0 +1,122 @@
+use super::*;
+use parse;
+
+const MON: &str = "mon
+    name leafward
+    abilities
+        strike
+            drain 2
+        move
+        strike
+            drain 3
+    parts
+        leaf
+            leaf
+";
+
+fn lines(path: &str) -> Vec<usize> {
+    let nodes = parse(MON).unwrap();
+    let query: Query = path.parse().unwrap();
+    query.find(&nodes).iter().map(|node| node.line).collect()
+}
+
+#[test]
+fn labels() {
+    assert_eq!(lines("mon/abilities/strike/drain"), [5, 8]);
+    assert_eq!(lines("mon/name/leafward"), [2]);
+    assert!(lines("mon/health").is_empty());
+    assert_eq!(lines("\"mon\"/name"), [2]);
+}
+
+#[test]
+fn wildcards() {
+    assert_eq!(lines("mon/*"), [2, 3, 9]);
+    assert_eq!(lines("mon/abilities/*/drain"), [5, 8]);
+    assert_eq!(lines("**/drain"), [5, 8]);
+    assert_eq!(lines("**/leaf"), [10, 11]);
+    assert_eq!(lines("**/leaf/**"), [11]);
+    assert_eq!(lines("mon/abilities/**"), [4, 5, 5, 6, 7, 8, 8]);
+    assert_eq!(lines("**/**/drain"), [5, 8]);
+}
+
+#[test]
+fn indexes() {
+    assert_eq!(lines("mon/abilities/strike[1]/drain"), [8]);
+    assert_eq!(lines("mon/abilities/*[1]"), [6]);
+    assert!(lines("mon/abilities/strike[2]").is_empty());
+    assert_eq!(lines("**/leaf[0]"), [10, 11]);
+}
+
+#[test]
+fn node_methods() {
+    let nodes = parse(MON).unwrap();
+    let mon = &nodes[0];
+    let drain = mon.get("abilities/strike/drain").unwrap();
+    assert_eq!(drain.children[0].name(), Some("2"));
+    assert_eq!(mon.get_all("abilities/strike").len(), 2);
+    assert_eq!(mon.query(&"name".parse().unwrap()).len(), 1);
+    assert_eq!(mon.get("abilities["), None);
+    assert!(mon.get_all("").is_empty());
+}
+
+#[test]
+fn errors() {
+    let error = |path: &str| path.parse::<Query>().unwrap_err();
+    assert_eq!(
+        error(""),
+        QueryError {
+            column: 1,
+            problem: "expected a step",
+        }
+    );
+    assert_eq!(
+        error("mon//name"),
+        QueryError {
+            column: 5,
+            problem: "expected a step",
+        }
+    );
+    assert_eq!(
+        error("mon/"),
+        QueryError {
+            column: 5,
+            problem: "expected a step",
+        }
+    );
+    assert_eq!(
+        error("mon/a[x]"),
+        QueryError {
+            column: 7,
+            problem: "expected a number and `]`",
+        }
+    );
+    assert_eq!(
+        error("mon/a[]"),
+        QueryError {
+            column: 7,
+            problem: "expected a number and `]`",
+        }
+    );
+    assert_eq!(
+        error("**[1]"),
+        QueryError {
+            column: 3,
+            problem: "expected `/` between steps",
+        }
+    );
+    assert_eq!(
+        error("a*"),
+        QueryError {
+            column: 2,
+            problem: "expected `/` between steps",
+        }
+    );
+    assert_eq!(
+        error("\"a"),
+        QueryError {
+            column: 1,
+            problem: "unterminated quote",
+        }
+    );
+    assert_eq!(error("]").to_string(), "unexpected character at column 1");
+}
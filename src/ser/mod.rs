@@ -0,0 +1,470 @@
+//! Writing Rust values as termpose, with serde
+//!
+//! Values are laid out the way [`de`](crate::de) reads them back:
+//!
+//!  - structs and maps are a node for each field or key, with the value in its
+//!    tail: `health 50`; fields which are `None` are left out;
+//!  - sequences and tuples are their items as siblings: `tags plant healer`;
+//!  - enum variants are their name, with any contents as its tail;
+//!  - within a sequence, structs are headed by their name, and other sequences
+//!    are an s-list of their items: `grid (1 2) (3 4)`.
+//!
+//! As a single item in parens stands for itself, a sequence within a sequence
+//! can't hold just one other sequence, or `None`, and be read back the same.
+//!
+//! [`to_string`] writes these out with nodes that only have labels in their
+//! tails on one line, and everything else indented, as people tend to write
+//! termpose by hand. Labels are only quoted where they have to be.
+
+use node::Node;
+use printer::Style;
+use serde::ser::{
+    self, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+use std::error::Error as StdError;
+use std::fmt;
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+/// The longest lines [`to_string`] puts several labels on
+const WIDTH: usize = 80;
+
+/// A value that can't be written as termpose
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Write a value as a termpose document
+///
+/// # Errors
+///
+/// If the value can't be written as termpose, such as a map with keys that
+/// aren't labels, or if its `Serialize` impl fails.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    to_nodes(value).map(|nodes| Style::shallow(WIDTH).print(&nodes))
+}
+
+/// Turn a value into the top-level nodes of a document
+///
+/// # Errors
+///
+/// As for [`to_string`].
+pub fn to_nodes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<Node<'static>>, Error> {
+    value.serialize(Serializer).map(|value| value.nodes)
+}
+
+fn label<N: Into<String>>(name: N) -> Node<'static> {
+    Node::new(name.into(), "", 0)
+}
+
+/// What a value was, and the tail it's written as
+struct Value {
+    kind: Kind,
+    nodes: Vec<Node<'static>>,
+}
+
+enum Kind {
+    /// `None`, which leaves out the field it's in
+    Nothing,
+
+    /// A single node
+    Single,
+    Seq,
+    Struct(&'static str),
+    Map,
+}
+
+impl Value {
+    fn single(node: Node<'static>) -> Self {
+        Self {
+            kind: Kind::Single,
+            nodes: vec![node],
+        }
+    }
+
+    /// The value as a single node, where it's an item of a sequence
+    fn item(self) -> Node<'static> {
+        let mut nodes = self.nodes.into_iter();
+        let mut node = match (self.kind, nodes.next()) {
+            (Kind::Single, Some(node)) => return node,
+            (Kind::Struct(name), first) => {
+                let mut node = label(name);
+                node.children.extend(first);
+                node
+            }
+            (Kind::Seq, Some(first)) if first.is_label() => first,
+            (Kind::Seq, Some(first)) => Node::list(first, "", 0),
+            (Kind::Map, Some(first)) => {
+                let mut node = Node::nil("", 0);
+                node.children.push(first);
+                node
+            }
+            (_, _) => return Node::nil("", 0),
+        };
+
+        node.children.extend(nodes);
+        node
+    }
+
+    /// The value as a single label, where it's a key
+    fn key(self) -> Result<String, Error> {
+        match (self.kind, &self.nodes[..]) {
+            (Kind::Single, [node]) if node.is_label() => {
+                Ok(node.name().unwrap_or_default().to_owned())
+            }
+            _ => Err(ser::Error::custom("keys must be labels")),
+        }
+    }
+}
+
+struct Serializer;
+
+macro_rules! labels {
+    ($($method:ident $type:ty)*) => {
+        $(
+            fn $method(self, v: $type) -> Result<Value, Error> {
+                Ok(Value::single(label(v.to_string())))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = Seq;
+    type SerializeTuple = Seq;
+    type SerializeTupleStruct = Seq;
+    type SerializeTupleVariant = Seq;
+    type SerializeMap = Entries;
+    type SerializeStruct = Entries;
+    type SerializeStructVariant = Entries;
+
+    labels! {
+        serialize_bool bool
+        serialize_i8 i8
+        serialize_i16 i16
+        serialize_i32 i32
+        serialize_i64 i64
+        serialize_i128 i128
+        serialize_u8 u8
+        serialize_u16 u16
+        serialize_u32 u32
+        serialize_u64 u64
+        serialize_u128 u128
+        serialize_f32 f32
+        serialize_f64 f64
+        serialize_char char
+        serialize_str &str
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        str::from_utf8(v)
+            .map_err(ser::Error::custom)
+            .and_then(|v| self.serialize_str(v))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value {
+            kind: Kind::Nothing,
+            nodes: vec![],
+        })
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value {
+            kind: Kind::Seq,
+            nodes: vec![],
+        })
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::single(label(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let mut node = label(variant);
+        node.children = value.serialize(self)?.nodes;
+        Ok(Value::single(node))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Seq, Error> {
+        Ok(Seq {
+            variant: None,
+            nodes: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Seq, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Seq, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Seq, Error> {
+        Ok(Seq {
+            variant: Some(variant),
+            nodes: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Entries, Error> {
+        Ok(Entries::new(Kind::Map, len.unwrap_or_default()))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Entries, Error> {
+        Ok(Entries::new(Kind::Struct(name), len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Entries, Error> {
+        let mut entries = Entries::new(Kind::Struct(variant), len);
+        entries.variant = true;
+        Ok(entries)
+    }
+}
+
+/// Writes sequences, tuples, and tuple variants
+struct Seq {
+    /// The name of the variant, for a tuple variant
+    variant: Option<&'static str>,
+    nodes: Vec<Node<'static>>,
+}
+
+impl Seq {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.nodes.push(value.serialize(Serializer)?.item());
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        match self.variant {
+            Some(variant) => {
+                let mut node = label(variant);
+                node.children = self.nodes;
+                Value::single(node)
+            }
+            None => Value {
+                kind: Kind::Seq,
+                nodes: self.nodes,
+            },
+        }
+    }
+}
+
+impl SerializeSeq for Seq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTuple for Seq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleStruct for Seq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleVariant for Seq {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+/// Writes maps, structs, and struct variants
+struct Entries {
+    kind: Kind,
+
+    /// Whether this is a struct variant, written as a single node
+    variant: bool,
+    nodes: Vec<Node<'static>>,
+
+    /// The key of the entry being written, once it has been
+    key: Option<String>,
+}
+
+impl Entries {
+    fn new(kind: Kind, len: usize) -> Self {
+        Self {
+            kind,
+            variant: false,
+            nodes: Vec::with_capacity(len),
+            key: None,
+        }
+    }
+
+    fn entry<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        let value = value.serialize(Serializer)?;
+        if let Kind::Nothing = value.kind {
+            return Ok(());
+        }
+
+        let mut node = label(key);
+        node.children = value.nodes;
+        self.nodes.push(node);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        match self.kind {
+            Kind::Struct(variant) if self.variant => {
+                let mut node = label(variant);
+                node.children = self.nodes;
+                Value::single(node)
+            }
+            kind => Value {
+                kind,
+                nodes: self.nodes,
+            },
+        }
+    }
+}
+
+impl SerializeMap for Entries {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(Serializer)?.key()?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let Some(key) = self.key.take() else {
+            return Err(ser::Error::custom("value given before its key"));
+        };
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStruct for Entries {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entry(key.into(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStructVariant for Entries {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.entry(key.into(), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
@@ -0,0 +1,140 @@
+use super::*;
+use de::from_str;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Doc {
+    mon: Mon,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Mon {
+    name: String,
+    health: u32,
+    speed: f64,
+    tags: Vec<String>,
+    abilities: Vec<Ability>,
+    resist: BTreeMap<String, f32>,
+    shape: Shape,
+    grid: Vec<Vec<u8>>,
+    owner: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Ability {
+    name: String,
+    power: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Shape {
+    Point,
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+    Line(u32, u32),
+}
+
+fn mon() -> Doc {
+    Doc {
+        mon: Mon {
+            name: "courageous leafward".into(),
+            health: 50,
+            speed: 1.5,
+            tags: vec!["plant".into(), "healer:2".into()],
+            abilities: vec![
+                Ability {
+                    name: "strike".into(),
+                    power: Some(3),
+                },
+                Ability {
+                    name: "move".into(),
+                    power: None,
+                },
+            ],
+            resist: vec![("fire".into(), 0.5), ("water".into(), 2.0)]
+                .into_iter()
+                .collect(),
+            shape: Shape::Rect { w: 2, h: 3 },
+            grid: vec![vec![1, 2], vec![3, 4]],
+            owner: None,
+        },
+    }
+}
+
+#[test]
+fn idiomatic_layout() {
+    assert_eq!(
+        to_string(&mon()).unwrap(),
+        "mon
+    name \"courageous leafward\"
+    health 50
+    speed 1.5
+    tags plant healer\\:2
+    abilities
+        Ability
+            name strike
+            power 3
+        Ability
+            name move
+    resist
+        fire 0.5
+        water 2
+    shape
+        rect
+            w 2
+            h 3
+    grid
+        1 2
+        3 4
+"
+    );
+}
+
+#[test]
+fn round_trips() {
+    let doc = mon();
+    assert_eq!(from_str::<Doc>(&to_string(&doc).unwrap()), Ok(doc));
+
+    for shape in [
+        Shape::Point,
+        Shape::Circle(1),
+        Shape::Line(1, 2),
+        Shape::Rect { w: 1, h: 2 },
+    ] {
+        assert_eq!(from_str::<Shape>(&to_string(&shape).unwrap()), Ok(shape));
+    }
+
+    let nested = vec![vec![], vec![Some(1), None], vec![None, Some(2)]];
+    assert_eq!(
+        from_str::<Vec<Vec<Option<u8>>>>(&to_string(&nested).unwrap()),
+        Ok(nested)
+    );
+
+    let text = "two\nlines";
+    assert_eq!(
+        from_str::<String>(&to_string(text).unwrap()),
+        Ok(text.into())
+    );
+}
+
+#[test]
+fn values() {
+    assert_eq!(to_string(&5).unwrap(), "5\n");
+    assert_eq!(to_string("").unwrap(), "\"\"\n");
+    assert_eq!(to_string(&(1, "a b", 'c')).unwrap(), "1\n\"a b\"\nc\n");
+    assert_eq!(to_string(&()).unwrap(), "");
+    assert_eq!(to_string(&Shape::Circle(2)).unwrap(), "circle 2\n");
+}
+
+#[test]
+fn keys_must_be_labels() {
+    let mut map = BTreeMap::new();
+    map.insert(vec![1, 2], 3);
+    assert_eq!(
+        to_string(&map),
+        Err(Error {
+            message: "keys must be labels".into()
+        })
+    );
+}
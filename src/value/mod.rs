@@ -0,0 +1,167 @@
+//! Reading `key value` nodes as Rust values
+//!
+//! SPEC §1 leaves what labels mean to the application, so nothing here is
+//! done by the parser: these are helpers to call on nodes whose tail is
+//! expected to be a single label, such as `health 50`. For whole structs, see
+//! [`FromTermpose`](crate::FromTermpose).
+
+use convert::{parsed, single, DecodeError};
+use node::Node;
+use std::error::Error as StdError;
+use std::fmt;
+use std::ptr;
+use std::str::FromStr;
+use walk::Order;
+
+#[cfg(test)]
+mod tests;
+
+/// A node which doesn't hold the value asked for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueError {
+    /// Where the value was looked for, as a [query](crate::Query) from the node
+    /// it was asked of, including that node's own label: the labels of the
+    /// nodes matched on the way down, or for a missing value, the path asked
+    /// for
+    pub path: String,
+
+    /// What's wrong, and on which line
+    pub error: DecodeError,
+}
+
+impl ValueError {
+    /// The line the problem is on, or for a missing value, the line of the
+    /// node it was looked for under
+    pub fn line(&self) -> usize {
+        self.error.line()
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}`: {}", self.path, self.error)
+    }
+}
+
+impl StdError for ValueError {}
+
+impl Node<'_> {
+    /// The label in this node's tail, which must be nothing else
+    ///
+    /// # Errors
+    ///
+    /// If the tail is empty, or has more than a single label in it.
+    pub fn only_label(&self) -> Result<&str, ValueError> {
+        single(&self.children, self.line)
+            .map(|node| node.name().unwrap_or_default())
+            .map_err(|error| ValueError {
+                path: self.step(),
+                error,
+            })
+    }
+
+    /// Parse the single label in this node's tail
+    ///
+    /// # Errors
+    ///
+    /// If the tail isn't a single label, or it doesn't parse as a `T`.
+    pub fn parse<T: FromStr>(&self) -> Result<T, ValueError>
+    where
+        T::Err: fmt::Display,
+    {
+        self.parse_at(self.step())
+    }
+
+    /// Read the single label in this node's tail as an integer
+    ///
+    /// # Errors
+    ///
+    /// If the tail isn't a single label, or it isn't an integer that fits.
+    pub fn as_i64(&self) -> Result<i64, ValueError> {
+        self.parse()
+    }
+
+    /// Read the single label in this node's tail as a number
+    ///
+    /// # Errors
+    ///
+    /// If the tail isn't a single label, or it isn't a number.
+    pub fn as_f64(&self) -> Result<f64, ValueError> {
+        self.parse()
+    }
+
+    /// Read the single label in this node's tail as `true` or `false`
+    ///
+    /// # Errors
+    ///
+    /// If the tail isn't a single label, or it's neither `true` nor `false`.
+    pub fn as_bool(&self) -> Result<bool, ValueError> {
+        self.parse()
+    }
+
+    /// Find a node by path, as with [`Node::get`], and parse the single label in
+    /// its tail
+    ///
+    /// Errors are reported at the whole path down from this node, which the
+    /// accessors on the node found can't know.
+    ///
+    /// # Errors
+    ///
+    /// If nothing matches the path, or the first node that does doesn't hold a
+    /// `T`.
+    pub fn value<T: FromStr>(&self, path: &str) -> Result<T, ValueError>
+    where
+        T::Err: fmt::Display,
+    {
+        let Some(node) = self.get(path) else {
+            return Err(ValueError {
+                path: format!("{}/{path}", self.step()),
+                error: DecodeError::MissingKey {
+                    key: path.into(),
+                    line: self.line,
+                },
+            });
+        };
+
+        node.parse_at(self.path_to(node))
+    }
+
+    /// Parse the single label in this node's tail, reporting errors at `path`
+    fn parse_at<T: FromStr>(&self, path: String) -> Result<T, ValueError>
+    where
+        T::Err: fmt::Display,
+    {
+        parsed(&self.children, self.line).map_err(|error| ValueError { path, error })
+    }
+
+    /// The steps down from this node to one in its tail, as matched rather
+    /// than as asked for
+    fn path_to(&self, node: &Node) -> String {
+        let indices = self
+            .depth_first(Order::Pre)
+            .find(|walked| ptr::eq(walked.node, node))
+            .map(|walked| walked.path)
+            .unwrap_or_default();
+
+        let mut path = self.step();
+        let mut tail = &self.children;
+        for i in indices {
+            path.push('/');
+            path.push_str(&tail[i].step());
+            tail = &tail[i].children;
+        }
+        path
+    }
+
+    /// This node as a step of a query: its label, quoted if need be, or `*` if
+    /// its head isn't a label
+    fn step(&self) -> String {
+        match self.name() {
+            Some(name) if name.is_empty() || name.contains(['/', '*', '[', ']', '"']) => {
+                format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            Some(name) => name.into(),
+            None => "*".into(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use super::*;
+use parse;
+
+const MON: &str = "mon
+    health 50
+    speed 1.5
+    wild true
+    name \"courageous leafward\"
+    abilities
+        strike
+            drain two
+    parts leaf root
+    \"a/b\" c
+";
+
+#[test]
+fn typed() {
+    let nodes = parse(MON).unwrap();
+    let mon = &nodes[0];
+    assert_eq!(mon.get("health").unwrap().as_i64(), Ok(50));
+    assert_eq!(mon.get("speed").unwrap().as_f64(), Ok(1.5));
+    assert_eq!(mon.get("wild").unwrap().as_bool(), Ok(true));
+    assert_eq!(
+        mon.get("name").unwrap().only_label(),
+        Ok("courageous leafward")
+    );
+    assert_eq!(mon.get("health").unwrap().parse::<u8>(), Ok(50));
+    assert_eq!(mon.value::<u16>("health"), Ok(50));
+}
+
+#[test]
+fn errors() {
+    let nodes = parse(MON).unwrap();
+    let mon = &nodes[0];
+
+    let error = mon.value::<i64>("abilities/strike/drain").unwrap_err();
+    assert_eq!(error.path, "mon/abilities/strike/drain");
+    assert_eq!(error.line(), 8);
+    assert_eq!(
+        error.to_string(),
+        "`mon/abilities/strike/drain`: invalid label `two` at line 8: invalid digit found in string"
+    );
+
+    assert_eq!(
+        mon.get("parts").unwrap().only_label(),
+        Err(ValueError {
+            path: "parts".into(),
+            error: DecodeError::Expected {
+                what: "a single label",
+                line: 9,
+            },
+        })
+    );
+    assert_eq!(
+        mon.get("abilities").unwrap().as_bool(),
+        Err(ValueError {
+            path: "abilities".into(),
+            error: DecodeError::Expected {
+                what: "a single label",
+                line: 7,
+            },
+        })
+    );
+    assert_eq!(
+        mon.value::<bool>("health"),
+        Err(ValueError {
+            path: "mon/health".into(),
+            error: DecodeError::Invalid {
+                label: "50".into(),
+                reason: "provided string was not `true` or `false`".into(),
+                line: 2,
+            },
+        })
+    );
+    assert_eq!(
+        mon.value::<u8>("hp"),
+        Err(ValueError {
+            path: "mon/hp".into(),
+            error: DecodeError::MissingKey {
+                key: "hp".into(),
+                line: 1,
+            },
+        })
+    );
+    assert_eq!(
+        mon.value::<i64>("*/strike/drain").unwrap_err().path,
+        "mon/abilities/strike/drain"
+    );
+    assert_eq!(
+        mon.value::<i64>("**/drain").unwrap_err().path,
+        "mon/abilities/strike/drain"
+    );
+    assert_eq!(mon.value::<i64>("\"a/b\"").unwrap_err().path, "mon/\"a/b\"");
+    assert_eq!(
+        mon.get("\"a/b\"").unwrap().as_i64().unwrap_err().path,
+        "\"a/b\""
+    );
+    assert_eq!(
+        mon.get("abilities/strike")
+            .unwrap()
+            .as_i64()
+            .unwrap_err()
+            .error,
+        DecodeError::Expected {
+            what: "a single label",
+            line: 8,
+        }
+    );
+}
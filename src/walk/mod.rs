@@ -0,0 +1,241 @@
+//! Walking over every node in a tree
+//!
+//! Nodes are found by their path: the index of each s-list in its parent's
+//! tail, on the way down from where the walk started. A walk over a single
+//! node starts with that node, at depth 0 with an empty path; a walk over a
+//! list of nodes, such as a document, starts with each of them, at depth 1.
+//! Paths from a document can be given to an [`Editor`](crate::Editor).
+//!
+//! Walks only go through tails, never into s-lists used as heads.
+
+use node::Node;
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod tests;
+
+/// A node found on a walk
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Walked<'n, 'a: 'n> {
+    pub node: &'n Node<'a>,
+    pub path: Vec<usize>,
+}
+
+impl Walked<'_, '_> {
+    /// How many s-lists down the node is from where the walk started
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+/// When a depth-first walk yields each node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Before the nodes in its tail
+    Pre,
+
+    /// After the nodes in its tail
+    Post,
+}
+
+/// Walks down each node's tail before going on to its next sibling
+#[derive(Clone, Debug)]
+pub struct DepthFirst<'n, 'a: 'n> {
+    order: Order,
+
+    /// Nodes still to walk, last first, and whether their tails are already on
+    /// the stack
+    stack: Vec<(Walked<'n, 'a>, bool)>,
+}
+
+impl<'n, 'a> DepthFirst<'n, 'a> {
+    /// Walk over a list of nodes, such as a document
+    pub fn new(nodes: &'n [Node<'a>], order: Order) -> Self {
+        let mut walk = Self {
+            order,
+            stack: vec![],
+        };
+        walk.push_tail(nodes, &[]);
+        walk
+    }
+
+    fn push_tail(&mut self, nodes: &'n [Node<'a>], parent: &[usize]) {
+        for (i, node) in nodes.iter().enumerate().rev() {
+            let mut path = parent.to_vec();
+            path.push(i);
+            self.stack.push((Walked { node, path }, false));
+        }
+    }
+}
+
+impl<'n, 'a> Iterator for DepthFirst<'n, 'a> {
+    type Item = Walked<'n, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (walked, pushed) = self.stack.pop()?;
+            if pushed || walked.node.children.is_empty() {
+                return Some(walked);
+            }
+
+            match self.order {
+                Order::Pre => {
+                    self.push_tail(&walked.node.children, &walked.path);
+                    return Some(walked);
+                }
+                Order::Post => {
+                    let (children, path) = (&walked.node.children, walked.path.clone());
+                    self.stack.push((walked, true));
+                    self.push_tail(children, &path);
+                }
+            }
+        }
+    }
+}
+
+/// Walks over every node at one depth before going any deeper
+#[derive(Clone, Debug)]
+pub struct BreadthFirst<'n, 'a: 'n> {
+    queue: VecDeque<Walked<'n, 'a>>,
+}
+
+impl<'n, 'a> BreadthFirst<'n, 'a> {
+    /// Walk over a list of nodes, such as a document
+    pub fn new(nodes: &'n [Node<'a>]) -> Self {
+        let mut walk = Self {
+            queue: VecDeque::new(),
+        };
+        walk.push_tail(nodes, &[]);
+        walk
+    }
+
+    fn push_tail(&mut self, nodes: &'n [Node<'a>], parent: &[usize]) {
+        for (i, node) in nodes.iter().enumerate() {
+            let mut path = parent.to_vec();
+            path.push(i);
+            self.queue.push_back(Walked { node, path });
+        }
+    }
+}
+
+impl<'n, 'a> Iterator for BreadthFirst<'n, 'a> {
+    type Item = Walked<'n, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walked = self.queue.pop_front()?;
+        self.push_tail(&walked.node.children, &walked.path);
+        Some(walked)
+    }
+}
+
+/// Called on entering and leaving each node of a walk
+///
+/// Nodes are entered before, and left after, the nodes in their tail.
+pub trait Visitor {
+    /// Called before the node's tail is walked, which is skipped if this
+    /// returns false
+    fn enter(&mut self, _node: &Node, _path: &[usize]) -> bool {
+        true
+    }
+
+    /// Called after the node's tail is walked, or skipped
+    fn leave(&mut self, _node: &Node, _path: &[usize]) {}
+
+    /// Walk over a list of nodes, such as a document
+    fn walk(&mut self, nodes: &[Node])
+    where
+        Self: Sized,
+    {
+        visit_tail(nodes, &mut vec![], self);
+    }
+}
+
+/// Called on entering and leaving each node of a walk, able to change them
+///
+/// Nodes are entered before, and left after, the nodes in their tail. Changes
+/// made to a node's tail on entering it are walked over.
+pub trait VisitorMut {
+    /// Called before the node's tail is walked, which is skipped if this
+    /// returns false
+    fn enter(&mut self, _node: &mut Node, _path: &[usize]) -> bool {
+        true
+    }
+
+    /// Called after the node's tail is walked, or skipped
+    fn leave(&mut self, _node: &mut Node, _path: &[usize]) {}
+
+    /// Walk over a list of nodes, such as a document
+    fn walk(&mut self, nodes: &mut [Node])
+    where
+        Self: Sized,
+    {
+        visit_tail_mut(nodes, &mut vec![], self);
+    }
+}
+
+fn visit<V: Visitor>(node: &Node, path: &mut Vec<usize>, visitor: &mut V) {
+    if visitor.enter(node, path) {
+        visit_tail(&node.children, path, visitor);
+    }
+    visitor.leave(node, path);
+}
+
+fn visit_tail<V: Visitor>(nodes: &[Node], path: &mut Vec<usize>, visitor: &mut V) {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+        visit(node, path, visitor);
+        path.pop();
+    }
+}
+
+fn visit_mut<V: VisitorMut>(node: &mut Node, path: &mut Vec<usize>, visitor: &mut V) {
+    if visitor.enter(node, path) {
+        visit_tail_mut(&mut node.children, path, visitor);
+    }
+    visitor.leave(node, path);
+}
+
+fn visit_tail_mut<V: VisitorMut>(nodes: &mut [Node], path: &mut Vec<usize>, visitor: &mut V) {
+    for (i, node) in nodes.iter_mut().enumerate() {
+        path.push(i);
+        visit_mut(node, path, visitor);
+        path.pop();
+    }
+}
+
+impl<'a> Node<'a> {
+    /// Walk depth-first over this node and everything in its tail
+    pub fn depth_first(&self, order: Order) -> DepthFirst<'_, 'a> {
+        DepthFirst {
+            order,
+            stack: vec![(
+                Walked {
+                    node: self,
+                    path: vec![],
+                },
+                false,
+            )],
+        }
+    }
+
+    /// Walk breadth-first over this node and everything in its tail
+    pub fn breadth_first(&self) -> BreadthFirst<'_, 'a> {
+        BreadthFirst {
+            queue: vec![Walked {
+                node: self,
+                path: vec![],
+            }]
+            .into(),
+        }
+    }
+
+    /// Walk over this node and everything in its tail with a [`Visitor`]
+    pub fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visit(self, &mut vec![], visitor);
+    }
+
+    /// Walk over this node and everything in its tail with a [`VisitorMut`]
+    pub fn visit_mut<V: VisitorMut>(&mut self, visitor: &mut V) {
+        visit_mut(self, &mut vec![], visitor);
+    }
+}
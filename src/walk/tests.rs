@@ -0,0 +1,143 @@
+use super::*;
+use node::Head;
+use parse;
+
+const MON: &str = "mon
+    name leafward
+    abilities move strike
+health 50
+";
+
+fn names<'n, I: Iterator<Item = Walked<'n, 'n>>>(walk: I) -> Vec<(&'n str, Vec<usize>)> {
+    walk.map(|walked| (walked.node.name().unwrap(), walked.path))
+        .collect()
+}
+
+#[test]
+fn depth_first() {
+    let nodes = parse(MON).unwrap();
+    assert_eq!(
+        names(DepthFirst::new(&nodes, Order::Pre)),
+        [
+            ("mon", vec![0]),
+            ("name", vec![0, 0]),
+            ("leafward", vec![0, 0, 0]),
+            ("abilities", vec![0, 1]),
+            ("move", vec![0, 1, 0]),
+            ("strike", vec![0, 1, 1]),
+            ("health", vec![1]),
+            ("50", vec![1, 0]),
+        ]
+    );
+    assert_eq!(
+        names(DepthFirst::new(&nodes, Order::Post)),
+        [
+            ("leafward", vec![0, 0, 0]),
+            ("name", vec![0, 0]),
+            ("move", vec![0, 1, 0]),
+            ("strike", vec![0, 1, 1]),
+            ("abilities", vec![0, 1]),
+            ("mon", vec![0]),
+            ("50", vec![1, 0]),
+            ("health", vec![1]),
+        ]
+    );
+    assert_eq!(
+        names(nodes[0].depth_first(Order::Post)).last(),
+        Some(&("mon", vec![]))
+    );
+}
+
+#[test]
+fn breadth_first() {
+    let nodes = parse(MON).unwrap();
+    assert_eq!(
+        names(BreadthFirst::new(&nodes)),
+        [
+            ("mon", vec![0]),
+            ("health", vec![1]),
+            ("name", vec![0, 0]),
+            ("abilities", vec![0, 1]),
+            ("50", vec![1, 0]),
+            ("leafward", vec![0, 0, 0]),
+            ("move", vec![0, 1, 0]),
+            ("strike", vec![0, 1, 1]),
+        ]
+    );
+
+    let depths: Vec<usize> = nodes[0].breadth_first().map(|w| w.depth()).collect();
+    assert_eq!(depths, [0, 1, 1, 2, 2, 2]);
+}
+
+/// Writes out where it goes, without going into `abilities`
+#[derive(Default)]
+struct Trace(Vec<String>);
+
+impl Visitor for Trace {
+    fn enter(&mut self, node: &Node, path: &[usize]) -> bool {
+        let name = node.name().unwrap();
+        self.0.push(format!("enter {name} {path:?}"));
+        name != "abilities"
+    }
+
+    fn leave(&mut self, node: &Node, _path: &[usize]) {
+        self.0.push(format!("leave {}", node.name().unwrap()));
+    }
+}
+
+#[test]
+fn visitor() {
+    let nodes = parse(MON).unwrap();
+    let mut trace = Trace::default();
+    nodes[0].visit(&mut trace);
+    assert_eq!(
+        trace.0,
+        [
+            "enter mon []",
+            "enter name [0]",
+            "enter leafward [0, 0]",
+            "leave leafward",
+            "leave name",
+            "enter abilities [1]",
+            "leave abilities",
+            "leave mon",
+        ]
+    );
+
+    let mut trace = Trace::default();
+    trace.walk(&nodes);
+    assert_eq!(trace.0[0], "enter mon [0]");
+    assert_eq!(trace.0.len(), 12);
+}
+
+/// Doubles every number, and counts the nodes it leaves
+#[derive(Default)]
+struct Double(usize);
+
+impl VisitorMut for Double {
+    fn enter(&mut self, node: &mut Node, _path: &[usize]) -> bool {
+        if let Some(Head::Label(label)) = &mut node.head {
+            if let Ok(n) = label.parse::<u32>() {
+                *label = (n * 2).to_string().into();
+            }
+        }
+        true
+    }
+
+    fn leave(&mut self, _node: &mut Node, _path: &[usize]) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn visitor_mut() {
+    let mut nodes = parse(MON).unwrap();
+    let mut double = Double::default();
+    double.walk(&mut nodes);
+    assert_eq!(double.0, 8);
+    assert_eq!(nodes[1].children[0].name(), Some("100"));
+
+    nodes[1].visit_mut(&mut double);
+    assert_eq!(double.0, 10);
+    assert_eq!(nodes[1].children[0].name(), Some("200"));
+}
@@ -0,0 +1,48 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+/// A file of its own under the temp directory, holding `source`
+fn file(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("nompose-cli-{}-{name}.tp", process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+fn nompose(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_nompose"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn fmt_check() {
+    let messy = file("messy", "a   b\n    c\n");
+    let canonical = file("canonical", "a\n    b\n    c\n");
+    assert_eq!(nompose(&["fmt", "--check", messy.to_str().unwrap()]), 1);
+    assert_eq!(nompose(&["fmt", "--check", canonical.to_str().unwrap()]), 0);
+    assert_eq!(fs::read_to_string(&messy).unwrap(), "a   b\n    c\n");
+    fs::remove_file(messy).unwrap();
+    fs::remove_file(canonical).unwrap();
+}
+
+#[test]
+fn fmt_rewrites() {
+    let messy = file("rewrite", "a   b\n    c\n");
+    assert_eq!(nompose(&["fmt", messy.to_str().unwrap()]), 0);
+    assert_eq!(fs::read_to_string(&messy).unwrap(), "a\n    b\n    c\n");
+    assert_eq!(nompose(&["fmt", "--check", messy.to_str().unwrap()]), 0);
+    fs::remove_file(messy).unwrap();
+}
+
+#[test]
+fn unreadable() {
+    let missing = env::temp_dir().join("nompose-cli-missing/none.tp");
+    assert_eq!(nompose(&["fmt", "--check", missing.to_str().unwrap()]), 2);
+    assert_eq!(nompose(&["fmt", missing.to_str().unwrap()]), 2);
+}
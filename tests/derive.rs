@@ -0,0 +1,228 @@
+#![cfg(feature = "derive")]
+
+extern crate nompose;
+
+use nompose::{parse, write_value, DecodeError, FromTermpose, ToTermpose};
+
+const MON: &str = "mon
+    name \"courageous leafward\"
+    hp 50
+    abilities
+        Ability strike
+            drain 2
+        Ability move
+    position 3 4
+    element grass
+    shape
+        rect
+            w 2
+            h 3
+";
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Doc {
+    mon: Mon,
+}
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Mon {
+    name: String,
+    #[termpose(rename = "hp")]
+    health: u32,
+    #[termpose(default)]
+    stride: u32,
+    #[termpose(default = "speed")]
+    speed: f64,
+    abilities: Vec<Ability>,
+    position: Point,
+    #[termpose(flatten)]
+    kind: Kind,
+    shape: Shape,
+}
+
+fn speed() -> f64 {
+    1.5
+}
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Ability {
+    #[termpose(positional)]
+    name: String,
+    drain: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Point(i32, i32);
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+struct Kind {
+    element: Element,
+    owner: Option<String>,
+}
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+#[termpose(rename_all = "lowercase")]
+enum Element {
+    Grass,
+    Fire,
+}
+
+#[derive(Debug, PartialEq, FromTermpose, ToTermpose)]
+#[termpose(rename_all = "kebab-case")]
+enum Shape {
+    Circle(u32),
+    Rect {
+        w: u32,
+        h: u32,
+    },
+    #[termpose(rename = "line")]
+    Segment(u32, u32),
+    OddlyShaped,
+}
+
+fn mon() -> Doc {
+    Doc {
+        mon: Mon {
+            name: "courageous leafward".into(),
+            health: 50,
+            stride: 0,
+            speed: 1.5,
+            abilities: vec![
+                Ability {
+                    name: "strike".into(),
+                    drain: Some(2),
+                },
+                Ability {
+                    name: "move".into(),
+                    drain: None,
+                },
+            ],
+            position: Point(3, 4),
+            kind: Kind {
+                element: Element::Grass,
+                owner: None,
+            },
+            shape: Shape::Rect { w: 2, h: 3 },
+        },
+    }
+}
+
+fn decode<T: FromTermpose>(input: &str) -> Result<T, DecodeError> {
+    T::from_tail(&parse(input).unwrap(), 0)
+}
+
+#[test]
+fn decodes() {
+    assert_eq!(decode::<Doc>(MON), Ok(mon()));
+}
+
+#[test]
+fn encodes() {
+    assert_eq!(
+        write_value(&mon()),
+        "mon
+    name \"courageous leafward\"
+    hp 50
+    stride 0
+    speed 1.5
+    abilities
+        Ability
+            strike
+            drain 2
+        Ability move
+    position 3 4
+    element grass
+    shape
+        rect
+            w 2
+            h 3
+"
+    );
+    assert_eq!(decode::<Doc>(&write_value(&mon())), Ok(mon()));
+}
+
+#[test]
+fn enums() {
+    assert_eq!(decode::<Shape>("circle 2\n"), Ok(Shape::Circle(2)));
+    assert_eq!(decode::<Shape>("line 1 2\n"), Ok(Shape::Segment(1, 2)));
+    assert_eq!(decode::<Shape>("oddly-shaped\n"), Ok(Shape::OddlyShaped));
+    assert_eq!(write_value(&Shape::Segment(1, 2)), "line 1 2\n");
+    assert_eq!(
+        decode::<Shape>("\nsquare 2\n"),
+        Err(DecodeError::UnknownVariant {
+            name: "square".into(),
+            line: 2,
+        })
+    );
+    assert_eq!(
+        decode::<Element>("fire\nwater\n"),
+        Err(DecodeError::Expected {
+            what: "a single variant",
+            line: 2,
+        })
+    );
+}
+
+#[test]
+fn missing_keys() {
+    let err = decode::<Doc>(&MON.replace("    hp 50\n", "")).unwrap_err();
+    assert_eq!(
+        err,
+        DecodeError::MissingKey {
+            key: "hp".into(),
+            line: 1,
+        }
+    );
+    assert_eq!(err.to_string(), "missing key `hp` under line 1");
+
+    assert_eq!(
+        decode::<Doc>(&MON.replace("    element grass\n", "")),
+        Err(DecodeError::MissingKey {
+            key: "element".into(),
+            line: 1,
+        })
+    );
+    assert_eq!(
+        decode::<Doc>(&MON.replace("position 3 4", "position 3")),
+        Err(DecodeError::MissingKey {
+            key: "1".into(),
+            line: 8,
+        })
+    );
+}
+
+#[test]
+fn unknown_keys() {
+    assert_eq!(
+        decode::<Doc>(&MON.replace("hp 50", "hp 50\n    mood calm")),
+        Err(DecodeError::UnknownKey {
+            key: "mood".into(),
+            line: 4,
+        })
+    );
+    assert_eq!(
+        decode::<Doc>(&MON.replace("drain 2", "drain 2\n            cost 1")),
+        Err(DecodeError::UnknownKey {
+            key: "cost".into(),
+            line: 7,
+        })
+    );
+    assert_eq!(
+        decode::<Doc>(&MON.replace("position 3 4", "position 3 4 5")),
+        Err(DecodeError::UnknownKey {
+            key: "5".into(),
+            line: 8,
+        })
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    assert_eq!(
+        decode::<Doc>(&MON.replace("hp 50", "hp 50\n    hp 60")),
+        Err(DecodeError::DuplicateKey {
+            key: "hp".into(),
+            line: 4,
+        })
+    );
+}